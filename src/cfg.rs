@@ -1,11 +1,22 @@
-use crate::utils::create_folder;
-use clap::Parser;
+use crate::lagrangian::compression::Compression;
+use crate::lagrangian::encoding::IndicesEncoding;
+use crate::lagrangian::nodes::{AreaMethod, NormalWeighting};
+use crate::lagrangian::quantization::QuantizationDtype;
+use crate::lagrangian::split::SplitMode;
+use crate::stl::triangle::DegeneracyCriteria;
+use crate::utils::{create_folder, Vec3f};
+use clap::{Parser, Subcommand};
 use std::error::Error;
 use std::{collections::HashMap, fs, path, string::String};
 
 #[derive(Parser)]
 #[command(author, version, about, long_about=None)] // Read from `Cargo.toml`
+#[command(subcommand_negates_reqs = true, args_conflicts_with_subcommands = true)]
 pub struct Args {
+    /// Command to run on existing LNAS, instead of converting STLs
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     /// Directories to use
     #[arg(short, long, num_args(0..), help = "Directories with STLs to use")]
     pub dir: Vec<String>,
@@ -15,8 +26,13 @@ pub struct Args {
     pub file: Vec<String>,
 
     /// Output folder
-    #[arg(short, long, help = "Output filename for .lnas")]
-    pub output: String,
+    #[arg(
+        short,
+        long,
+        required = true,
+        help = "Output filename for .lnas (binary layout if extension is .lnasb)"
+    )]
+    pub output: Option<String>,
 
     /// Overwrite existing files
     #[arg(long, action, help = "Overwrite existing files")]
@@ -25,9 +41,274 @@ pub struct Args {
     /// Copy STLs or not
     #[arg(long, action, help = "Copy STLs to output folder")]
     pub copy_stl: bool,
+
+    /// Make triangles orientation consistent
+    #[arg(
+        long,
+        action,
+        help = "Make triangles winding consistent, orienting closed bodies normals outwards"
+    )]
+    pub fix_orientation: bool,
+
+    /// Shift geometry to origin
+    #[arg(
+        long,
+        action,
+        help = "Shift geometry bounding box center to origin, recording the shift in LNAS (for large coordinates)"
+    )]
+    pub origin_shift: bool,
+
+    /// Minimum triangle area
+    #[arg(long, default_value_t = 1e-5, help = "Minimum triangle area")]
+    pub min_area: f32,
+
+    /// Minimum triangle area relative to the surface mean
+    #[arg(long, help = "Minimum triangle area relative to its surface mean area")]
+    pub min_relative_area: Option<f32>,
+
+    /// Minimum triangle angle
+    #[arg(long, help = "Minimum triangle internal angle, in degrees")]
+    pub min_angle: Option<f32>,
+
+    /// Maximum triangle aspect ratio
+    #[arg(
+        long,
+        help = "Maximum triangle aspect ratio (1 for equilateral triangles)"
+    )]
+    pub max_aspect_ratio: Option<f32>,
+
+    /// Maximum perimeter of holes to fill
+    #[arg(
+        long,
+        help = "Fill holes (any boundary loop, including ends of open surfaces) with perimeter up to this value"
+    )]
+    pub fill_holes: Option<f32>,
+
+    /// Add filled holes to a dedicated surface
+    #[arg(
+        long,
+        action,
+        help = "Add filled holes patches to 'filled_holes' surface instead of adjacent surface"
+    )]
+    pub filled_holes_surface: bool,
+
+    /// Save vertices normals
+    #[arg(
+        long,
+        value_enum,
+        help = "Save vertices normals, weighting triangles normals by"
+    )]
+    pub vertices_normals: Option<NormalWeighting>,
+
+    /// Save vertices areas
+    #[arg(
+        long,
+        value_enum,
+        help = "Save vertices areas, distributing triangles areas by"
+    )]
+    pub vertices_areas: Option<AreaMethod>,
+
+    /// Compression of arrays
+    #[arg(
+        long,
+        value_enum,
+        help = "Compress LNAS arrays before base64 encoding with"
+    )]
+    pub compression: Option<Compression>,
+
+    /// Quantization of vertices
+    #[arg(
+        long,
+        value_enum,
+        help = "Quantize vertices coordinates relative to bounding box with"
+    )]
+    pub quantize_vertices: Option<QuantizationDtype>,
+
+    /// Encoding of indices arrays
+    #[arg(
+        long,
+        value_enum,
+        help = "Encode triangles and surfaces indices arrays with"
+    )]
+    pub indices_encoding: Option<IndicesEncoding>,
+
+    /// Save triangles centroids, areas and normals
+    #[arg(long, action, help = "Save triangles centroids, areas and normals")]
+    pub triangles_properties: bool,
+
+    /// Lattice spacing to check mesh against
+    #[arg(
+        long,
+        help = "Check edges and triangles sizes against lattice spacing dx"
+    )]
+    pub dx: Option<f32>,
+
+    /// Maximum edge length relative to dx
+    #[arg(
+        long,
+        default_value_t = 1.0,
+        help = "Maximum triangle edge length, relative to dx, in lattice check"
+    )]
+    pub max_dx_ratio: f32,
+
+    /// Refinement configuration file
+    #[arg(long, help = "YAML file with refinement of each surface")]
+    pub refinement: Option<String>,
+
+    /// Angle to detect feature edges
+    #[arg(
+        long,
+        default_value_t = 30.0,
+        help = "Minimum angle between triangles normals for an edge to be a feature, in degrees"
+    )]
+    pub feature_angle: f32,
+
+    /// Split surfaces in sub-surfaces
+    #[arg(
+        long,
+        value_enum,
+        help = "Split each surface in sub-surfaces named '<surface>_<n>', by"
+    )]
+    pub split_surfaces: Option<SplitMode>,
+
+    /// Check triangles intersections
+    #[arg(
+        long,
+        action,
+        help = "Check for triangles crossing each other, such as interpenetrating surfaces"
+    )]
+    pub check_intersections: bool,
+
+    /// STL to export intersecting triangles
+    #[arg(
+        long,
+        help = "Export intersecting triangles to this STL, when checking intersections"
+    )]
+    pub intersections_stl: Option<String>,
+
+    /// Distance threshold for surfaces proximity
+    #[arg(
+        long,
+        help = "Report minimum distance between surfaces and regions closer than this threshold"
+    )]
+    pub proximity: Option<f32>,
+
+    /// Report geometric statistics
+    #[arg(
+        long,
+        action,
+        help = "Report area, projected area, bounding box, centroid and volume of each surface, also saved to '<output>.stats.yaml'"
+    )]
+    pub stats: bool,
+
+    /// Direction to compute projected area along
+    #[arg(
+        long,
+        value_delimiter = ',',
+        allow_negative_numbers = true,
+        help = "Compute projected area along direction, considering occlusion (e.g. '1,0,0')"
+    )]
+    pub projected_area: Option<Vec<f32>>,
+
+    /// Projected area raster resolution
+    #[arg(
+        long,
+        default_value_t = 1000,
+        help = "Number of cells along largest side of projection, for projected area"
+    )]
+    pub projection_resolution: usize,
+
+    /// Surfaces attributes file
+    #[arg(
+        long,
+        help = "YAML file with attributes of each surface, saved to LNAS"
+    )]
+    pub surfaces_attributes: Option<String>,
+
+    /// Save metadata
+    #[arg(
+        long,
+        action,
+        help = "Save metadata (creation time, tool version, command line, sources hashes, bounding box and counts)"
+    )]
+    pub metadata: bool,
+
+    /// Length unit
+    #[arg(
+        long,
+        requires = "metadata",
+        help = "Length unit of geometry to record in metadata (e.g. 'm')"
+    )]
+    pub units: Option<String>,
+
+    /// Save feature edges
+    #[arg(long, action, help = "Save feature edges and corners to LNAS")]
+    pub save_features: bool,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Convert LNAS of a previous version (v0.2 onwards) to current version
+    Migrate {
+        /// LNAS to migrate
+        #[arg(help = "LNAS filename to migrate")]
+        input: String,
+
+        /// Migrated output
+        #[arg(
+            short,
+            long,
+            help = "Output filename for migrated .lnas (binary layout if extension is .lnasb)"
+        )]
+        output: String,
+
+        /// Overwrite existing output
+        #[arg(long, action, help = "Overwrite existing output file")]
+        overwrite: bool,
+    },
+    /// Check LNAS arrays, indices and version, exiting with non-zero code on problems
+    Validate {
+        /// LNAS to validate
+        #[arg(help = "LNAS filename to validate")]
+        input: String,
+    },
 }
 
 impl Args {
+    /// Output filename, required when converting STLs
+    pub fn output(&self) -> &str {
+        return self.output.as_deref().expect("Output is required");
+    }
+
+    pub fn degeneracy_criteria(&self) -> DegeneracyCriteria {
+        return DegeneracyCriteria {
+            min_area: self.min_area,
+            min_relative_area: self.min_relative_area,
+            min_angle: self.min_angle,
+            max_aspect_ratio: self.max_aspect_ratio,
+        };
+    }
+
+    pub fn projection_direction(&self) -> Option<Vec3f> {
+        return self.projected_area.as_ref().map(|d| {
+            if d.len() != 3 {
+                panic!(
+                    "Projected area direction must have 3 values, got {}",
+                    d.len()
+                );
+            }
+            Vec3f {
+                x: d[0],
+                y: d[1],
+                z: d[2],
+            }
+        });
+    }
+
+    pub fn stats_filename(&self) -> String {
+        return format!("{}.stats.yaml", self.output());
+    }
+
     pub fn folder_stls(&self, dir: &str) -> Vec<path::PathBuf> {
         let paths = fs::read_dir(dir).unwrap();
         let mut all_paths: Vec<path::PathBuf> = Vec::new();
@@ -76,7 +357,7 @@ impl Args {
     }
 
     pub fn save_stl_to_output_folder(&self) -> Result<(), Box<dyn Error>> {
-        let mut str_foldername_stl = self.output.to_owned();
+        let mut str_foldername_stl = self.output().to_owned();
         str_foldername_stl.push_str(".stls");
         let foldername = path::Path::new(str_foldername_stl.as_str());
         create_folder(foldername)?;
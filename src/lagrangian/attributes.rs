@@ -0,0 +1,110 @@
+use crate::lagrangian::split::SplitReport;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::{fs, path};
+
+/// Free-form properties of a surface, such as boundary condition type or wall roughness
+pub type SurfaceAttributes = BTreeMap<String, serde_yaml::Value>;
+
+/// Sidecar file with attributes of each surface
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SurfacesAttributesCfg {
+    pub surfaces: HashMap<String, SurfaceAttributes>,
+}
+
+impl SurfacesAttributesCfg {
+    pub fn from_file(filename: &path::Path) -> Result<SurfacesAttributesCfg, Box<dyn Error>> {
+        let file = fs::File::open(filename)?;
+        let cfg: SurfacesAttributesCfg = serde_yaml::from_reader(file)?;
+        return Ok(cfg);
+    }
+
+    /// Give attributes of split surfaces to each of their sub-surfaces.
+    ///
+    /// Sub-surfaces with their own attributes keep them
+    pub fn apply_split(&mut self, split_report: &SplitReport) {
+        for split in split_report.splits.iter() {
+            let surface_attributes = match self.surfaces.remove(&split.surface_name) {
+                Some(surface_attributes) => surface_attributes,
+                None => continue,
+            };
+            for (sub_surface_name, _) in split.sub_surfaces.iter() {
+                self.surfaces
+                    .entry(sub_surface_name.to_owned())
+                    .or_insert_with(|| surface_attributes.clone());
+            }
+        }
+    }
+}
+
+/// Attributes of existing surfaces, skipping the ones not found
+pub fn get_surfaces_attributes(
+    cfg: &SurfacesAttributesCfg,
+    surfaces: &HashMap<String, Vec<u32>>,
+) -> BTreeMap<String, SurfaceAttributes> {
+    let mut attributes: BTreeMap<String, SurfaceAttributes> = BTreeMap::new();
+    for (surface_name, surface_attributes) in cfg.surfaces.iter() {
+        if !surfaces.contains_key(surface_name) {
+            println!("Surface '{}' with attributes not found", surface_name);
+            continue;
+        }
+        attributes.insert(surface_name.to_owned(), surface_attributes.clone());
+    }
+    return attributes;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lagrangian::split::SurfaceSplit;
+
+    #[test]
+    fn check_surfaces_attributes_example() {
+        let cfg = SurfacesAttributesCfg::from_file(path::Path::new(
+            "examples/cfg/surfaces_attributes.yaml",
+        ))
+        .unwrap();
+        let mut surfaces: HashMap<String, Vec<u32>> = HashMap::new();
+        surfaces.insert("cube".to_string(), vec![0, 1]);
+        surfaces.insert("plane".to_string(), vec![2]);
+
+        let attributes = get_surfaces_attributes(&cfg, &surfaces);
+        // Sphere is not in surfaces
+        assert_eq!(attributes.len(), 2);
+        let cube = attributes.get("cube").unwrap();
+        assert_eq!(cube.get("boundary_condition").unwrap(), "wall");
+        assert_eq!(cube.get("roughness").unwrap().as_f64(), Some(0.001));
+        assert_eq!(cube.get("moving").unwrap().as_bool(), Some(false));
+    }
+
+    #[test]
+    fn check_surfaces_attributes_split() {
+        let mut cfg = SurfacesAttributesCfg::from_file(path::Path::new(
+            "examples/cfg/surfaces_attributes.yaml",
+        ))
+        .unwrap();
+        let mut moving_face = SurfaceAttributes::new();
+        moving_face.insert("moving".to_string(), serde_yaml::Value::Bool(true));
+        cfg.surfaces.insert("cube_1".to_string(), moving_face);
+        let split_report = SplitReport {
+            splits: vec![SurfaceSplit {
+                surface_name: "cube".to_string(),
+                sub_surfaces: vec![("cube_0".to_string(), 1), ("cube_1".to_string(), 1)],
+            }],
+        };
+        cfg.apply_split(&split_report);
+
+        let mut surfaces: HashMap<String, Vec<u32>> = HashMap::new();
+        surfaces.insert("cube_0".to_string(), vec![0]);
+        surfaces.insert("cube_1".to_string(), vec![1]);
+        let attributes = get_surfaces_attributes(&cfg, &surfaces);
+        assert_eq!(attributes.len(), 2);
+        let cube_0 = attributes.get("cube_0").unwrap();
+        assert_eq!(cube_0.get("boundary_condition").unwrap(), "wall");
+        // Explicit sub-surface attributes are kept
+        let cube_1 = attributes.get("cube_1").unwrap();
+        assert_eq!(cube_1.len(), 1);
+        assert_eq!(cube_1.get("moving").unwrap().as_bool(), Some(true));
+    }
+}
@@ -0,0 +1,582 @@
+use crate::lagrangian::format::{ArrayLNAS, FeaturesLNAS, GeometryLNAS, SurfaceLNAS, LNAS};
+use crate::lagrangian::quantization::{Quantization, QuantizationDtype};
+use crate::utils::create_folder_for_filename;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::io::{BufWriter, Write};
+use std::{fs, path};
+
+/// First bytes of binary LNAS files
+pub const LNASB_MAGIC: &[u8; 8] = b"LNASB\0\0\0";
+/// Sections data starts in multiples of this, so arrays may be read in place
+const SECTION_ALIGNMENT: usize = 8;
+
+const GEOMETRY_PREFIX: &str = "geometry/";
+const SURFACES_PREFIX: &str = "surfaces/";
+const FEATURES_PREFIX: &str = "features/";
+
+/// Type of values in a section
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SectionDtype {
+    F32 = 0,
+    U32 = 1,
+    U16 = 2,
+    /// Bytes of compressed or encoded arrays
+    U8 = 3,
+}
+
+impl SectionDtype {
+    fn from_u8(value: u8) -> Result<SectionDtype, Box<dyn Error>> {
+        return match value {
+            0 => Ok(SectionDtype::F32),
+            1 => Ok(SectionDtype::U32),
+            2 => Ok(SectionDtype::U16),
+            3 => Ok(SectionDtype::U8),
+            _ => Err(format!("Invalid section dtype {}", value).into()),
+        };
+    }
+}
+
+/// Named array of little-endian values, borrowed from LNAS
+struct Section<'a> {
+    name: String,
+    dtype: SectionDtype,
+    bytes: &'a [u8],
+}
+
+fn vertices_dtype(quantization: Option<&Quantization>) -> SectionDtype {
+    return match quantization {
+        None => SectionDtype::F32,
+        Some(quantization) => match quantization.dtype {
+            QuantizationDtype::U16 => SectionDtype::U16,
+            QuantizationDtype::U32 => SectionDtype::U32,
+        },
+    };
+}
+
+fn geometry_sections(geometry: &GeometryLNAS) -> Vec<(&str, SectionDtype, Option<&ArrayLNAS>)> {
+    let vertices_dtype = vertices_dtype(geometry.vertices_quantization.as_ref());
+    return vec![
+        ("vertices", vertices_dtype, Some(&geometry.vertices)),
+        ("triangles", SectionDtype::U32, Some(&geometry.triangles)),
+        (
+            "vertices_normals",
+            SectionDtype::F32,
+            geometry.vertices_normals.as_ref(),
+        ),
+        (
+            "vertices_areas",
+            SectionDtype::F32,
+            geometry.vertices_areas.as_ref(),
+        ),
+        (
+            "triangles_centroids",
+            SectionDtype::F32,
+            geometry.triangles_centroids.as_ref(),
+        ),
+        (
+            "triangles_areas",
+            SectionDtype::F32,
+            geometry.triangles_areas.as_ref(),
+        ),
+        (
+            "triangles_normals",
+            SectionDtype::F32,
+            geometry.triangles_normals.as_ref(),
+        ),
+    ];
+}
+
+fn get_sections(lnas_obj: &LNAS) -> Vec<Section<'_>> {
+    let mut sections: Vec<Section> = Vec::new();
+    for (name, dtype, array) in geometry_sections(&lnas_obj.geometry) {
+        if let Some(array) = array {
+            sections.push(Section {
+                name: format!("{}{}", GEOMETRY_PREFIX, name),
+                dtype,
+                bytes: &array.0,
+            });
+        }
+    }
+
+    let mut surface_names: Vec<&String> = lnas_obj.surfaces.keys().collect();
+    surface_names.sort();
+    for surface_name in surface_names.into_iter() {
+        sections.push(Section {
+            name: format!("{}{}", SURFACES_PREFIX, surface_name),
+            dtype: SectionDtype::U32,
+            bytes: &lnas_obj
+                .surfaces
+                .get(surface_name)
+                .unwrap()
+                .triangles_idxs
+                .0,
+        });
+    }
+
+    if let Some(features) = &lnas_obj.features {
+        for (name, array) in [("edges", &features.edges), ("corners", &features.corners)] {
+            sections.push(Section {
+                name: format!("{}{}", FEATURES_PREFIX, name),
+                dtype: SectionDtype::U32,
+                bytes: &array.0,
+            });
+        }
+    }
+    let compressed = lnas_obj.compression.is_some();
+    let indices_encoded = lnas_obj.indices_encoding.is_some();
+    for section in sections.iter_mut() {
+        section.dtype = stored_dtype(&section.name, section.dtype, compressed, indices_encoded);
+    }
+    return sections;
+}
+
+/// Dtype of section, depending on vertices quantization
+fn expected_dtype(
+    name: &str,
+    vertices_dtype: SectionDtype,
+) -> Result<SectionDtype, Box<dyn Error>> {
+    if let Some(field) = name.strip_prefix(GEOMETRY_PREFIX) {
+        return match field {
+            "vertices" => Ok(vertices_dtype),
+            "triangles" => Ok(SectionDtype::U32),
+            _ => Ok(SectionDtype::F32),
+        };
+    }
+    if name.starts_with(SURFACES_PREFIX) || name.starts_with(FEATURES_PREFIX) {
+        return Ok(SectionDtype::U32);
+    }
+    return Err(format!("Unknown section '{}'", name).into());
+}
+
+/// Dtype of section as stored, being bytes when compressed or when its indices are encoded
+fn stored_dtype(
+    name: &str,
+    dtype: SectionDtype,
+    compressed: bool,
+    indices_encoded: bool,
+) -> SectionDtype {
+    let is_indices =
+        name == format!("{}triangles", GEOMETRY_PREFIX) || name.starts_with(SURFACES_PREFIX);
+    if compressed || (indices_encoded && is_indices) {
+        return SectionDtype::U8;
+    }
+    return dtype;
+}
+
+fn aligned(offset: usize) -> usize {
+    return offset.div_ceil(SECTION_ALIGNMENT) * SECTION_ALIGNMENT;
+}
+
+fn string_bytes(value: &str) -> Vec<u8> {
+    let mut bytes: Vec<u8> = (value.len() as u32).to_le_bytes().to_vec();
+    bytes.extend(value.as_bytes());
+    return bytes;
+}
+
+/// Header with version, counts and the table of sections.
+///
+/// Layout (little-endian):
+/// - magic (8 bytes)
+/// - version (u32 length + UTF-8)
+/// - compression of sections (u32 length + UTF-8, empty if not compressed)
+/// - encoding of indices sections (u32 length + UTF-8, empty if not encoded)
+/// - vertices quantization dtype (u32 length + UTF-8, empty if not quantized),
+///   followed by its offset and scale (3 f64 each) when quantized
+/// - origin shift flag (u8, 1 if shifted), followed by the shift (3 f64) when shifted
+/// - metadata as YAML (u32 length + UTF-8, empty if absent)
+/// - surfaces attributes as YAML (u32 length + UTF-8, empty if absent)
+/// - number of vertices, triangles and surfaces (u64 each)
+/// - number of sections (u32)
+/// - for each section: name (u32 length + UTF-8), dtype (u8), offset and number of bytes (u64)
+fn header_bytes(
+    lnas_obj: &LNAS,
+    metadata: &str,
+    surfaces_attributes: &str,
+    counts: [usize; 3],
+    sections: &Vec<Section>,
+    offsets: &Vec<u64>,
+) -> Vec<u8> {
+    let mut header: Vec<u8> = LNASB_MAGIC.to_vec();
+    header.extend(string_bytes(&lnas_obj.version));
+    header.extend(string_bytes(
+        lnas_obj.compression.as_deref().unwrap_or_default(),
+    ));
+    header.extend(string_bytes(
+        lnas_obj.indices_encoding.as_deref().unwrap_or_default(),
+    ));
+    match &lnas_obj.geometry.vertices_quantization {
+        None => header.extend(string_bytes("")),
+        Some(quantization) => {
+            header.extend(string_bytes(quantization.dtype.name()));
+            for value in quantization.offset.iter().chain(quantization.scale.iter()) {
+                header.extend(value.to_le_bytes());
+            }
+        }
+    }
+    match &lnas_obj.geometry.origin_shift {
+        None => header.push(0u8),
+        Some(shift) => {
+            header.push(1u8);
+            for value in shift.iter() {
+                header.extend(value.to_le_bytes());
+            }
+        }
+    }
+    header.extend(string_bytes(metadata));
+    header.extend(string_bytes(surfaces_attributes));
+    for count in counts {
+        header.extend((count as u64).to_le_bytes());
+    }
+    header.extend((sections.len() as u32).to_le_bytes());
+    for (section, offset) in sections.iter().zip(offsets.iter()) {
+        header.extend(string_bytes(&section.name));
+        header.push(section.dtype as u8);
+        header.extend(offset.to_le_bytes());
+        header.extend((section.bytes.len() as u64).to_le_bytes());
+    }
+    return header;
+}
+
+/// Save LNAS in binary layout, with arrays as raw little-endian values
+pub fn save_lnasb(filename: &path::Path, lnas_obj: &LNAS) -> Result<(), Box<dyn Error>> {
+    let sections = get_sections(lnas_obj);
+    let metadata = match &lnas_obj.metadata {
+        Some(metadata) => serde_yaml::to_string(metadata)?,
+        None => String::new(),
+    };
+    let attributes = lnas_obj.get_surfaces_attributes();
+    let surfaces_attributes = match attributes.is_empty() {
+        true => String::new(),
+        false => serde_yaml::to_string(&attributes)?,
+    };
+    let counts = [
+        lnas_obj.n_vertices()?,
+        lnas_obj.n_triangles()?,
+        lnas_obj.surfaces.len(),
+    ];
+
+    // Offsets don't change header size, so compute it with placeholders first
+    let placeholder_offsets: Vec<u64> = vec![0; sections.len()];
+    let header_size = header_bytes(
+        lnas_obj,
+        &metadata,
+        &surfaces_attributes,
+        counts,
+        &sections,
+        &placeholder_offsets,
+    )
+    .len();
+    let mut offsets: Vec<u64> = Vec::with_capacity(sections.len());
+    let mut offset = aligned(header_size);
+    for section in sections.iter() {
+        offsets.push(offset as u64);
+        offset = aligned(offset + section.bytes.len());
+    }
+
+    create_folder_for_filename(filename)?;
+    let mut writer = BufWriter::new(fs::File::create(filename)?);
+    let header = header_bytes(
+        lnas_obj,
+        &metadata,
+        &surfaces_attributes,
+        counts,
+        &sections,
+        &offsets,
+    );
+    writer.write_all(&header)?;
+    let mut position = header.len();
+    for (section, offset) in sections.iter().zip(offsets.iter()) {
+        writer.write_all(&vec![0u8; *offset as usize - position])?;
+        writer.write_all(section.bytes)?;
+        position = *offset as usize + section.bytes.len();
+    }
+    writer.flush()?;
+    return Ok(());
+}
+
+/// Cursor to read header values
+struct HeaderReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> HeaderReader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], Box<dyn Error>> {
+        let end = self
+            .position
+            .checked_add(n)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or("Unexpected end of binary LNAS header")?;
+        let values = &self.bytes[self.position..end];
+        self.position = end;
+        return Ok(values);
+    }
+
+    fn read_u8(&mut self) -> Result<u8, Box<dyn Error>> {
+        return Ok(self.take(1)?[0]);
+    }
+
+    fn read_u32(&mut self) -> Result<u32, Box<dyn Error>> {
+        return Ok(u32::from_le_bytes(self.take(4)?.try_into()?));
+    }
+
+    fn read_u64(&mut self) -> Result<u64, Box<dyn Error>> {
+        return Ok(u64::from_le_bytes(self.take(8)?.try_into()?));
+    }
+
+    fn read_usize(&mut self) -> Result<usize, Box<dyn Error>> {
+        return Ok(usize::try_from(self.read_u64()?)?);
+    }
+
+    fn read_f64(&mut self) -> Result<f64, Box<dyn Error>> {
+        return Ok(f64::from_le_bytes(self.take(8)?.try_into()?));
+    }
+
+    fn read_string(&mut self) -> Result<String, Box<dyn Error>> {
+        let len = self.read_u32()? as usize;
+        return Ok(String::from_utf8(self.take(len)?.to_vec())?);
+    }
+}
+
+pub fn is_lnasb(bytes: &[u8]) -> bool {
+    return bytes.len() >= LNASB_MAGIC.len() && &bytes[..LNASB_MAGIC.len()] == LNASB_MAGIC;
+}
+
+/// Read LNAS from binary layout file
+pub fn read_lnasb(filename: &path::Path) -> Result<LNAS, Box<dyn Error>> {
+    return read_lnasb_bytes(&fs::read(filename)?);
+}
+
+/// Read LNAS from binary layout content, copying sections as they are
+pub fn read_lnasb_bytes(bytes: &[u8]) -> Result<LNAS, Box<dyn Error>> {
+    if !is_lnasb(bytes) {
+        return Err("Content is not a binary LNAS, missing magic bytes".into());
+    }
+    let mut header = HeaderReader {
+        bytes,
+        position: LNASB_MAGIC.len(),
+    };
+    let version = header.read_string()?;
+    let compression = header.read_string()?;
+    let indices_encoding = header.read_string()?;
+    let quantization_dtype = header.read_string()?;
+    let mut vertices_quantization: Option<Quantization> = None;
+    if !quantization_dtype.is_empty() {
+        let mut values = [0f64; 6];
+        for value in values.iter_mut() {
+            *value = header.read_f64()?;
+        }
+        vertices_quantization = Some(Quantization {
+            dtype: QuantizationDtype::from_name(&quantization_dtype)?,
+            offset: [values[0], values[1], values[2]],
+            scale: [values[3], values[4], values[5]],
+        });
+    }
+    let mut origin_shift: Option<[f64; 3]> = None;
+    if header.read_u8()? != 0 {
+        origin_shift = Some([header.read_f64()?, header.read_f64()?, header.read_f64()?]);
+    }
+    let metadata = header.read_string()?;
+    let surfaces_attributes = header.read_string()?;
+    let n_vertices = header.read_usize()?;
+    let n_triangles = header.read_usize()?;
+    let n_surfaces = header.read_usize()?;
+
+    let vertices_dtype = vertices_dtype(vertices_quantization.as_ref());
+    let n_sections = header.read_u32()?;
+    let mut geometry: HashMap<String, ArrayLNAS> = HashMap::new();
+    let mut surfaces: HashMap<String, SurfaceLNAS> = HashMap::new();
+    let mut features: HashMap<String, ArrayLNAS> = HashMap::new();
+    let mut names: HashSet<String> = HashSet::new();
+    for _ in 0..n_sections {
+        let name = header.read_string()?;
+        if !names.insert(name.clone()) {
+            return Err(format!("Section '{}' appears more than once", name).into());
+        }
+        let dtype = SectionDtype::from_u8(header.read_u8()?)?;
+        let offset = header.read_usize()?;
+        let n_bytes = header.read_usize()?;
+        let expected = stored_dtype(
+            &name,
+            expected_dtype(&name, vertices_dtype)?,
+            !compression.is_empty(),
+            !indices_encoding.is_empty(),
+        );
+        if dtype != expected {
+            return Err(format!(
+                "Section '{}' has dtype {:?}, expected {:?}",
+                name, dtype, expected
+            )
+            .into());
+        }
+        let end = offset
+            .checked_add(n_bytes)
+            .filter(|end| *end <= bytes.len())
+            .ok_or_else(|| format!("Section '{}' exceeds file size", name))?;
+        let array = ArrayLNAS(bytes[offset..end].to_vec());
+        if let Some(field) = name.strip_prefix(GEOMETRY_PREFIX) {
+            geometry.insert(field.to_string(), array);
+        } else if let Some(surface_name) = name.strip_prefix(SURFACES_PREFIX) {
+            surfaces.insert(
+                surface_name.to_string(),
+                SurfaceLNAS {
+                    triangles_idxs: array,
+                    attributes: None,
+                },
+            );
+        } else if let Some(field) = name.strip_prefix(FEATURES_PREFIX) {
+            features.insert(field.to_string(), array);
+        }
+    }
+
+    let mut required = |field: &str| {
+        return geometry
+            .remove(field)
+            .ok_or(format!("Missing geometry section '{}'", field));
+    };
+    let vertices = required("vertices")?;
+    let triangles = required("triangles")?;
+    let mut lnas_obj = LNAS {
+        version,
+        compression: match compression.is_empty() {
+            true => None,
+            false => Some(compression),
+        },
+        indices_encoding: match indices_encoding.is_empty() {
+            true => None,
+            false => Some(indices_encoding),
+        },
+        metadata: match metadata.is_empty() {
+            true => None,
+            false => Some(serde_yaml::from_str(&metadata)?),
+        },
+        geometry: GeometryLNAS {
+            vertices,
+            vertices_quantization,
+            origin_shift,
+            triangles,
+            vertices_normals: geometry.remove("vertices_normals"),
+            vertices_areas: geometry.remove("vertices_areas"),
+            triangles_centroids: geometry.remove("triangles_centroids"),
+            triangles_areas: geometry.remove("triangles_areas"),
+            triangles_normals: geometry.remove("triangles_normals"),
+        },
+        surfaces,
+        features: match (features.remove("edges"), features.remove("corners")) {
+            (Some(edges), Some(corners)) => Some(FeaturesLNAS { edges, corners }),
+            _ => None,
+        },
+    };
+    if !surfaces_attributes.is_empty() {
+        lnas_obj.set_surfaces_attributes(serde_yaml::from_str(&surfaces_attributes)?);
+    }
+    if lnas_obj.n_vertices()? != n_vertices
+        || lnas_obj.n_triangles()? != n_triangles
+        || lnas_obj.surfaces.len() != n_surfaces
+    {
+        return Err("Binary LNAS counts don't match its sections".into());
+    }
+    return Ok(lnas_obj);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lagrangian::compression::Compression;
+    use crate::lagrangian::encoding::IndicesEncoding;
+    use crate::lagrangian::features::detect_feature_edges;
+    use crate::lagrangian::format::get_lnas_obj_save;
+    use crate::lagrangian::nodes::{get_vertices_normals, NormalWeighting};
+    use crate::lagrangian::test_utils::{get_joined_stls, get_lnas_obj};
+
+    #[test]
+    fn check_lnasb_roundtrip() {
+        let (vertices, triangles, surfaces) = get_joined_stls(&["cube", "plane"]);
+        let mut lnas_obj = get_lnas_obj_save(&vertices, &triangles, &surfaces);
+        lnas_obj.set_vertices_normals(&get_vertices_normals(
+            &vertices,
+            &triangles,
+            NormalWeighting::Area,
+        ));
+        lnas_obj.set_features(&detect_feature_edges(&vertices, &triangles, 30f32));
+        lnas_obj.set_origin_shift(&[500000f64, 7400000f64, 0f64]);
+
+        let filename = path::Path::new("output/binary/plane_cube.lnasb");
+        save_lnasb(filename, &lnas_obj).unwrap();
+        let read_obj = read_lnasb(filename).unwrap();
+        assert!(read_obj == lnas_obj);
+        assert_eq!(read_obj.get_surfaces().unwrap(), surfaces);
+
+        // Raw arrays are smaller than base64 text
+        let yaml_size = serde_yaml::to_string(&lnas_obj).unwrap().len() as u64;
+        assert!(fs::metadata(filename).unwrap().len() < yaml_size);
+    }
+
+    #[test]
+    fn check_lnasb_compressed() {
+        let (vertices, triangles, surfaces) = get_joined_stls(&["terrain"]);
+        let mut lnas_obj = get_lnas_obj_save(&vertices, &triangles, &surfaces);
+        lnas_obj
+            .encode_indices(IndicesEncoding::DeltaZigzagVarint)
+            .unwrap();
+        lnas_obj.quantize_vertices(QuantizationDtype::U16).unwrap();
+        let quantized_vertices = lnas_obj.get_vertices().unwrap();
+        // Only encoded indices are saved as bytes before compression
+        let dtypes: Vec<SectionDtype> = get_sections(&lnas_obj).iter().map(|s| s.dtype).collect();
+        assert_eq!(dtypes[0], SectionDtype::U16);
+        assert!(dtypes[1..].iter().all(|d| *d == SectionDtype::U8));
+        lnas_obj.compress(Compression::Zlib).unwrap();
+        assert!(get_sections(&lnas_obj)
+            .iter()
+            .all(|s| s.dtype == SectionDtype::U8));
+
+        let filename = path::Path::new("output/binary/terrain_zlib.lnasb");
+        save_lnasb(filename, &lnas_obj).unwrap();
+        let read_obj = read_lnasb(filename).unwrap();
+        assert_eq!(read_obj.compression, Some("zlib".to_string()));
+        assert_eq!(
+            read_obj.indices_encoding,
+            Some("delta-zigzag-varint".to_string())
+        );
+        assert!(read_obj == lnas_obj);
+        assert_eq!(read_obj.get_vertices().unwrap(), quantized_vertices);
+        assert!(read_obj.get_triangles().unwrap() == triangles);
+    }
+
+    #[test]
+    fn check_lnasb_invalid_file() {
+        let filename = path::Path::new("examples/stl/cube.stl");
+        assert!(read_lnasb(filename).is_err());
+    }
+
+    #[test]
+    fn check_lnasb_corrupted_header() {
+        let lnas_obj = get_lnas_obj(&["cube"]);
+        let mut sections = get_sections(&lnas_obj);
+        let counts = [8, 12, 1];
+        let offsets: Vec<u64> = vec![u64::MAX; sections.len()];
+        let header = header_bytes(&lnas_obj, "", "", counts, &sections, &offsets);
+        // Truncated header and section offset overflowing with its size
+        assert!(read_lnasb_bytes(&header[..header.len() - 1]).is_err());
+        assert!(read_lnasb_bytes(&header).is_err());
+
+        // Triangles saved as f32, with sections inside header so only the dtype is wrong
+        sections[1].dtype = SectionDtype::F32;
+        let offsets: Vec<u64> = vec![0; sections.len()];
+        let header = header_bytes(&lnas_obj, "", "", counts, &sections, &offsets);
+        match read_lnasb_bytes(&header) {
+            Ok(_) => panic!("Section with wrong dtype was read"),
+            Err(e) => assert!(e.to_string().contains("dtype")),
+        }
+
+        // Vertices section name repeated on triangles section
+        let mut sections = get_sections(&lnas_obj);
+        sections[1].name = sections[0].name.clone();
+        let header = header_bytes(&lnas_obj, "", "", counts, &sections, &offsets);
+        match read_lnasb_bytes(&header) {
+            Ok(_) => panic!("Duplicated section was read"),
+            Err(e) => assert!(e.to_string().contains("more than once")),
+        }
+    }
+}
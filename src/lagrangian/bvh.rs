@@ -0,0 +1,300 @@
+use crate::lagrangian::mesh::triangle_points;
+use crate::utils::{Vec3f, Vec3u};
+
+/// Maximum number of items in a leaf node
+const LEAF_SIZE: usize = 4;
+
+/// Axis aligned bounding box
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3f,
+    pub max: Vec3f,
+}
+
+impl Aabb {
+    pub fn from_points(points: &[Vec3f]) -> Aabb {
+        let mut aabb = Aabb {
+            min: points[0],
+            max: points[0],
+        };
+        for p in points[1..].iter() {
+            aabb = aabb.union(&Aabb { min: *p, max: *p });
+        }
+        return aabb;
+    }
+
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        return Aabb {
+            min: Vec3f {
+                x: self.min.x.min(other.min.x),
+                y: self.min.y.min(other.min.y),
+                z: self.min.z.min(other.min.z),
+            },
+            max: Vec3f {
+                x: self.max.x.max(other.max.x),
+                y: self.max.y.max(other.max.y),
+                z: self.max.z.max(other.max.z),
+            },
+        };
+    }
+
+    /// Box grown by `margin` in all directions
+    pub fn expand(&self, margin: f32) -> Aabb {
+        let margin_vec = Vec3f {
+            x: margin,
+            y: margin,
+            z: margin,
+        };
+        return Aabb {
+            min: self.min - margin_vec,
+            max: self.max + margin_vec,
+        };
+    }
+
+    pub fn overlaps(&self, other: &Aabb) -> bool {
+        return self.min.x <= other.max.x
+            && other.min.x <= self.max.x
+            && self.min.y <= other.max.y
+            && other.min.y <= self.max.y
+            && self.min.z <= other.max.z
+            && other.min.z <= self.max.z;
+    }
+
+    /// Distance between boxes, zero when they overlap
+    pub fn distance(&self, other: &Aabb) -> f32 {
+        let gap = Vec3f {
+            x: (self.min.x - other.max.x)
+                .max(other.min.x - self.max.x)
+                .max(0f32),
+            y: (self.min.y - other.max.y)
+                .max(other.min.y - self.max.y)
+                .max(0f32),
+            z: (self.min.z - other.max.z)
+                .max(other.min.z - self.max.z)
+                .max(0f32),
+        };
+        return gap.norm();
+    }
+
+    pub fn center(&self) -> Vec3f {
+        let mut center = self.min + self.max;
+        center.divide(2f32);
+        return center;
+    }
+}
+
+#[derive(Clone, Debug)]
+enum BvhNode {
+    /// Items `items[start..end]`
+    Leaf {
+        aabb: Aabb,
+        start: usize,
+        end: usize,
+    },
+    Inner {
+        aabb: Aabb,
+        left: usize,
+        right: usize,
+    },
+}
+
+impl BvhNode {
+    fn aabb(&self) -> &Aabb {
+        return match self {
+            BvhNode::Leaf { aabb, .. } => aabb,
+            BvhNode::Inner { aabb, .. } => aabb,
+        };
+    }
+}
+
+/// Bounding volume hierarchy, to find items which boxes overlap a query box
+#[derive(Clone, Debug)]
+pub struct Bvh {
+    nodes: Vec<BvhNode>,
+    /// Items indexes, ordered so leaves reference contiguous ranges
+    items: Vec<usize>,
+    aabbs: Vec<Aabb>,
+}
+
+impl Bvh {
+    pub fn new(aabbs: Vec<Aabb>) -> Bvh {
+        let mut bvh = Bvh {
+            nodes: Vec::new(),
+            items: (0..aabbs.len()).collect(),
+            aabbs,
+        };
+        if !bvh.aabbs.is_empty() {
+            bvh.build(0, bvh.items.len());
+        }
+        return bvh;
+    }
+
+    /// BVH over triangles, with items as triangles indexes
+    pub fn from_triangles(vertices: &Vec<Vec3f>, triangles: &Vec<Vec3u>) -> Bvh {
+        let aabbs: Vec<Aabb> = triangles
+            .iter()
+            .map(|t| Aabb::from_points(&triangle_points(vertices, t)))
+            .collect();
+        return Bvh::new(aabbs);
+    }
+
+    pub fn item_aabb(&self, item: usize) -> &Aabb {
+        return &self.aabbs[item];
+    }
+
+    /// Build node for `items[start..end]`, splitting at the median of the longest axis
+    fn build(&mut self, start: usize, end: usize) -> usize {
+        let mut aabb = self.aabbs[self.items[start]];
+        for item in self.items[start + 1..end].iter() {
+            aabb = aabb.union(&self.aabbs[*item]);
+        }
+        let node_idx = self.nodes.len();
+        if end - start <= LEAF_SIZE {
+            self.nodes.push(BvhNode::Leaf { aabb, start, end });
+            return node_idx;
+        }
+        // Placeholder, replaced when children are built
+        self.nodes.push(BvhNode::Leaf { aabb, start, end });
+
+        let size = aabb.max - aabb.min;
+        let axis_value = |v: Vec3f| {
+            if size.x >= size.y && size.x >= size.z {
+                return v.x;
+            } else if size.y >= size.z {
+                return v.y;
+            }
+            return v.z;
+        };
+        let aabbs = &self.aabbs;
+        let mid = (start + end) / 2;
+        self.items[start..end].select_nth_unstable_by(mid - start, |a, b| {
+            axis_value(aabbs[*a].center()).total_cmp(&axis_value(aabbs[*b].center()))
+        });
+        let left = self.build(start, mid);
+        let right = self.build(mid, end);
+        self.nodes[node_idx] = BvhNode::Inner { aabb, left, right };
+        return node_idx;
+    }
+
+    /// Items which boxes overlap `aabb`
+    pub fn query(&self, aabb: &Aabb) -> Vec<usize> {
+        let mut found: Vec<usize> = Vec::new();
+        if self.nodes.is_empty() {
+            return found;
+        }
+        let mut stack: Vec<usize> = vec![0];
+        while let Some(node_idx) = stack.pop() {
+            let node = &self.nodes[node_idx];
+            if !node.aabb().overlaps(aabb) {
+                continue;
+            }
+            match node {
+                BvhNode::Leaf { start, end, .. } => {
+                    for item in self.items[*start..*end].iter() {
+                        if self.aabbs[*item].overlaps(aabb) {
+                            found.push(*item);
+                        }
+                    }
+                }
+                BvhNode::Inner { left, right, .. } => {
+                    stack.push(*left);
+                    stack.push(*right);
+                }
+            }
+        }
+        return found;
+    }
+
+    /// Item closest to `aabb` by `item_distance`, considering only distances below `max_distance`.
+    ///
+    /// `item_distance` must not be smaller than the distance between boxes.
+    pub fn nearest(
+        &self,
+        aabb: &Aabb,
+        max_distance: f32,
+        item_distance: impl Fn(usize) -> f32,
+    ) -> Option<(usize, f32)> {
+        let mut best: Option<(usize, f32)> = None;
+        let mut best_distance = max_distance;
+        if self.nodes.is_empty() {
+            return best;
+        }
+        let mut stack: Vec<usize> = vec![0];
+        while let Some(node_idx) = stack.pop() {
+            let node = &self.nodes[node_idx];
+            if node.aabb().distance(aabb) > best_distance {
+                continue;
+            }
+            match node {
+                BvhNode::Leaf { start, end, .. } => {
+                    for item in self.items[*start..*end].iter() {
+                        if self.aabbs[*item].distance(aabb) > best_distance {
+                            continue;
+                        }
+                        let distance = item_distance(*item);
+                        if distance <= best_distance {
+                            best_distance = distance;
+                            best = Some((*item, distance));
+                        }
+                    }
+                }
+                BvhNode::Inner { left, right, .. } => {
+                    // Visit closest child first, so farther one is more likely to be pruned
+                    let left_distance = self.nodes[*left].aabb().distance(aabb);
+                    let right_distance = self.nodes[*right].aabb().distance(aabb);
+                    if left_distance < right_distance {
+                        stack.push(*right);
+                        stack.push(*left);
+                    } else {
+                        stack.push(*left);
+                        stack.push(*right);
+                    }
+                }
+            }
+        }
+        return best;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point_aabb(x: f32, y: f32, z: f32) -> Aabb {
+        let p = Vec3f { x, y, z };
+        return Aabb { min: p, max: p }.expand(0.25);
+    }
+
+    #[test]
+    fn check_bvh_query_grid() {
+        let mut aabbs: Vec<Aabb> = Vec::new();
+        for i in 0..10 {
+            for j in 0..10 {
+                aabbs.push(point_aabb(i as f32, j as f32, 0f32));
+            }
+        }
+        let bvh = Bvh::new(aabbs.clone());
+
+        let query = point_aabb(4.5, 4.5, 0f32).expand(0.5);
+        let mut found = bvh.query(&query);
+        found.sort();
+        let mut expected: Vec<usize> = (0..aabbs.len())
+            .filter(|i| aabbs[*i].overlaps(&query))
+            .collect();
+        expected.sort();
+        assert_eq!(found, expected);
+        assert_eq!(found, vec![44, 45, 54, 55]);
+
+        assert!(bvh.query(&point_aabb(20f32, 0f32, 0f32)).is_empty());
+
+        let query = point_aabb(20f32, 3.2, 0f32);
+        let (nearest, distance) = bvh
+            .nearest(&query, f32::MAX, |i| aabbs[i].distance(&query))
+            .unwrap();
+        assert_eq!(nearest, 93);
+        assert!((distance - 10.5).abs() < 1e-5);
+        assert!(bvh
+            .nearest(&query, 1f32, |i| aabbs[i].distance(&query))
+            .is_none());
+    }
+}
@@ -0,0 +1,241 @@
+use crate::utils::{Vec3f, Vec3u};
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CleanupReport {
+    /// Triangles with repeated vertices after welding
+    pub n_collapsed: usize,
+    /// Copies of a triangle with same orientation
+    pub n_duplicated: usize,
+    /// Copies of a triangle with opposite orientation
+    pub n_opposite: usize,
+    /// Vertices only used by removed triangles
+    pub n_unused_vertices: usize,
+}
+
+impl CleanupReport {
+    /// Number of removed triangles
+    pub fn n_removed(&self) -> usize {
+        return self.n_collapsed + self.n_duplicated + self.n_opposite;
+    }
+
+    pub fn has_removed(&self) -> bool {
+        return self.n_removed() > 0 || self.n_unused_vertices > 0;
+    }
+}
+
+impl fmt::Display for CleanupReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(
+            f,
+            "Removed {} triangles ({} collapsed, {} duplicated, {} opposite duplicated) and {} unused vertices",
+            self.n_removed(),
+            self.n_collapsed,
+            self.n_duplicated,
+            self.n_opposite,
+            self.n_unused_vertices
+        );
+    }
+}
+
+fn is_collapsed(triangle: &Vec3u) -> bool {
+    return triangle.x == triangle.y || triangle.y == triangle.z || triangle.z == triangle.x;
+}
+
+/// Rotate triangle indexes so the smallest is first, keeping its orientation
+fn oriented_key(triangle: &Vec3u) -> [u32; 3] {
+    let (x, y, z) = (triangle.x, triangle.y, triangle.z);
+    if x <= y && x <= z {
+        return [x, y, z];
+    } else if y <= x && y <= z {
+        return [y, z, x];
+    }
+    return [z, x, y];
+}
+
+fn unoriented_key(triangle: &Vec3u) -> [u32; 3] {
+    let mut key = [triangle.x, triangle.y, triangle.z];
+    key.sort();
+    return key;
+}
+
+/// Indexes of triangles to keep after removing collapsed and duplicated ones.
+///
+/// Only the first of triangles with same vertices is kept, whatever their orientation, so zero
+/// thickness walls and baffles (same triangle with both orientations) keep one side.
+fn get_triangles_to_keep(triangles: &Vec<Vec3u>, report: &mut CleanupReport) -> Vec<bool> {
+    let mut keep: Vec<bool> = vec![true; triangles.len()];
+    let mut same_vertices: HashMap<[u32; 3], Vec<usize>> = HashMap::new();
+    for (t_idx, t) in triangles.iter().enumerate() {
+        if is_collapsed(t) {
+            keep[t_idx] = false;
+            report.n_collapsed += 1;
+            continue;
+        }
+        same_vertices
+            .entry(unoriented_key(t))
+            .or_default()
+            .push(t_idx);
+    }
+
+    for (_, t_idxs) in same_vertices.iter() {
+        if t_idxs.len() < 2 {
+            continue;
+        }
+        let first_key = oriented_key(&triangles[t_idxs[0]]);
+        for t_idx in t_idxs[1..].iter() {
+            keep[*t_idx] = false;
+            if oriented_key(&triangles[*t_idx]) == first_key {
+                report.n_duplicated += 1;
+            } else {
+                report.n_opposite += 1;
+            }
+        }
+    }
+    return keep;
+}
+
+/// Remove vertices not used by any triangle, keeping the order of the others
+fn remove_unused_vertices(
+    vertices: &Vec<Vec3f>,
+    triangles: &Vec<Vec3u>,
+) -> (Vec<Vec3f>, Vec<Vec3u>, usize) {
+    let mut used: Vec<bool> = vec![false; vertices.len()];
+    for t in triangles.iter() {
+        for idx in [t.x, t.y, t.z] {
+            used[idx as usize] = true;
+        }
+    }
+    let mut new_idxs: Vec<u32> = vec![0; vertices.len()];
+    let mut used_vertices: Vec<Vec3f> = Vec::with_capacity(vertices.len());
+    for (idx, v) in vertices.iter().enumerate() {
+        if used[idx] {
+            new_idxs[idx] = used_vertices.len() as u32;
+            used_vertices.push(*v);
+        }
+    }
+    let n_unused = vertices.len() - used_vertices.len();
+    let new_triangles: Vec<Vec3u> = triangles
+        .iter()
+        .map(|t| Vec3u {
+            x: new_idxs[t.x as usize],
+            y: new_idxs[t.y as usize],
+            z: new_idxs[t.z as usize],
+        })
+        .collect();
+    return (used_vertices, new_triangles, n_unused);
+}
+
+/// Remove collapsed and duplicated triangles and the vertices only they used,
+/// updating surfaces triangles indexes
+pub fn remove_invalid_triangles(
+    vertices: &Vec<Vec3f>,
+    triangles: &Vec<Vec3u>,
+    surfaces: &HashMap<String, Vec<u32>>,
+) -> (
+    Vec<Vec3f>,
+    Vec<Vec3u>,
+    HashMap<String, Vec<u32>>,
+    CleanupReport,
+) {
+    let mut report = CleanupReport::default();
+    let keep = get_triangles_to_keep(triangles, &mut report);
+
+    let mut new_idxs: Vec<Option<u32>> = vec![None; triangles.len()];
+    let mut clean_triangles: Vec<Vec3u> = Vec::with_capacity(triangles.len());
+    for (t_idx, t) in triangles.iter().enumerate() {
+        if keep[t_idx] {
+            new_idxs[t_idx] = Some(clean_triangles.len() as u32);
+            clean_triangles.push(*t);
+        }
+    }
+
+    let mut clean_surfaces: HashMap<String, Vec<u32>> = HashMap::new();
+    for (surface_name, triangles_idxs) in surfaces.iter() {
+        let surface_idxs: Vec<u32> = triangles_idxs
+            .iter()
+            .filter_map(|t_idx| new_idxs[*t_idx as usize])
+            .collect();
+        clean_surfaces.insert(surface_name.to_owned(), surface_idxs);
+    }
+
+    let (clean_vertices, clean_triangles, n_unused) =
+        remove_unused_vertices(vertices, &clean_triangles);
+    report.n_unused_vertices = n_unused;
+
+    return (clean_vertices, clean_triangles, clean_surfaces, report);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_triangle(x: u32, y: u32, z: u32) -> Vec3u {
+        return Vec3u { x, y, z };
+    }
+
+    fn get_vertices(n_vertices: usize) -> Vec<Vec3f> {
+        return (0..n_vertices)
+            .map(|i| Vec3f {
+                x: i as f32,
+                y: (i * i) as f32,
+                z: 0f32,
+            })
+            .collect();
+    }
+
+    #[test]
+    fn check_cleanup_keeps_valid() {
+        let vertices = get_vertices(4);
+        let triangles = vec![get_triangle(0, 1, 2), get_triangle(0, 2, 3)];
+        let surfaces = HashMap::from([("s".to_string(), vec![0, 1])]);
+        let (clean_vertices, clean_triangles, clean_surfaces, report) =
+            remove_invalid_triangles(&vertices, &triangles, &surfaces);
+
+        assert!(!report.has_removed());
+        assert_eq!(clean_vertices, vertices);
+        assert!(clean_triangles == triangles);
+        assert_eq!(clean_surfaces, surfaces);
+    }
+
+    #[test]
+    fn check_cleanup_removes_invalid() {
+        let triangles = vec![
+            get_triangle(0, 1, 2),
+            // Collapsed
+            get_triangle(0, 0, 2),
+            get_triangle(2, 3, 4),
+            // Duplicate of triangle 0, with rotated indexes
+            get_triangle(1, 2, 0),
+            // Opposite of triangle 2
+            get_triangle(2, 4, 3),
+            get_triangle(4, 5, 6),
+        ];
+        let surfaces = HashMap::from([
+            ("s0".to_string(), vec![0, 1, 2]),
+            ("s1".to_string(), vec![3, 4, 5]),
+        ]);
+        let vertices = get_vertices(8);
+        let (clean_vertices, clean_triangles, clean_surfaces, report) =
+            remove_invalid_triangles(&vertices, &triangles, &surfaces);
+
+        assert_eq!(report.n_collapsed, 1);
+        assert_eq!(report.n_duplicated, 1);
+        // Zero thickness wall keeps one side
+        assert_eq!(report.n_opposite, 1);
+        assert!(
+            clean_triangles
+                == vec![
+                    get_triangle(0, 1, 2),
+                    get_triangle(2, 3, 4),
+                    get_triangle(4, 5, 6)
+                ]
+        );
+        assert_eq!(clean_surfaces.get("s0").unwrap(), &vec![0, 1]);
+        assert_eq!(clean_surfaces.get("s1").unwrap(), &vec![2]);
+        // Last vertex was not used by any triangle
+        assert_eq!(report.n_unused_vertices, 1);
+        assert_eq!(clean_vertices, vertices[..7]);
+    }
+}
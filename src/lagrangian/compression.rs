@@ -0,0 +1,584 @@
+use clap::ValueEnum;
+use std::error::Error;
+use std::fmt;
+
+/// Compression of LNAS arrays, applied before base64 encoding
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Compression {
+    /// zlib (deflate) stream, as in RFC 1950
+    Zlib,
+}
+
+impl Compression {
+    /// Name saved in LNAS `compression` field
+    pub fn name(&self) -> &'static str {
+        return match self {
+            Compression::Zlib => "zlib",
+        };
+    }
+
+    pub fn from_name(name: &str) -> Result<Compression, Box<dyn Error>> {
+        return match name {
+            "zlib" => Ok(Compression::Zlib),
+            _ => Err(format!("Unknown compression '{}'", name).into()),
+        };
+    }
+
+    pub fn compress(&self, bytes: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+        return match self {
+            Compression::Zlib => Ok(zlib_compress(bytes)),
+        };
+    }
+
+    pub fn decompress(&self, bytes: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+        return match self {
+            Compression::Zlib => zlib_decompress(bytes),
+        };
+    }
+
+    /// Size of bytes once decompressed
+    pub fn decompressed_size(&self, bytes: &[u8]) -> Result<usize, Box<dyn Error>> {
+        return Ok(self.decompress(bytes)?.len());
+    }
+}
+
+/// Size of LNAS arrays before and after compression, in bytes
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct CompressionReport {
+    pub raw_size: usize,
+    pub compressed_size: usize,
+}
+
+impl fmt::Display for CompressionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let saved = 1f32 - self.compressed_size as f32 / (self.raw_size.max(1) as f32);
+        return write!(
+            f,
+            "Compressed arrays from {} to {} bytes ({:.1}% saved)",
+            self.raw_size,
+            self.compressed_size,
+            100f32 * saved
+        );
+    }
+}
+
+/// zlib header with deflate method, 32K window and maximum compression level
+const ZLIB_HEADER: [u8; 2] = [0x78, 0xDA];
+const WINDOW_SIZE: usize = 32768;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+/// Previous positions with same hash compared when looking for a match
+const MAX_CHAIN: usize = 128;
+const HASH_BITS: usize = 15;
+/// Bytes in each stored block, limited by its 16 bits length
+const MAX_STORED: usize = 65535;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+/// Order in which code lengths of the code lengths alphabet are saved in dynamic blocks
+const CODE_LENGTHS_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// Adler-32 checksum, as in RFC 1950
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    // Largest number of bytes before sums may overflow u32
+    for chunk in bytes.chunks(5552) {
+        for byte in chunk.iter() {
+            a += *byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    return (b << 16) | a;
+}
+
+/// Writer of bits, starting from the least significant bit of each byte
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    bit_buffer: u64,
+    n_bits: u32,
+}
+
+impl BitWriter {
+    fn write_bits(&mut self, value: u32, n: u32) {
+        self.bit_buffer |= (value as u64) << self.n_bits;
+        self.n_bits += n;
+        while self.n_bits >= 8 {
+            self.bytes.push(self.bit_buffer as u8);
+            self.bit_buffer >>= 8;
+            self.n_bits -= 8;
+        }
+    }
+
+    /// Huffman codes are saved starting from their most significant bit
+    fn write_code(&mut self, code: u32, length: u32) {
+        let reversed = code.reverse_bits() >> (32 - length);
+        self.write_bits(reversed, length);
+    }
+
+    /// Pad current byte with zeros
+    fn align_to_byte(&mut self) {
+        if self.n_bits > 0 {
+            self.write_bits(0, 8 - self.n_bits);
+        }
+    }
+}
+
+/// Code and its length of literal/length symbol in fixed Huffman blocks
+fn fixed_literal_code(symbol: u32) -> (u32, u32) {
+    return match symbol {
+        0..=143 => (0x30 + symbol, 8),
+        144..=255 => (0x190 + symbol - 144, 9),
+        256..=279 => (symbol - 256, 7),
+        _ => (0xC0 + symbol - 280, 8),
+    };
+}
+
+/// Index of the largest base below or equal to value
+fn base_index(bases: &[u16], value: usize) -> usize {
+    return bases
+        .iter()
+        .rposition(|base| *base as usize <= value)
+        .unwrap();
+}
+
+fn hash3(bytes: &[u8], position: usize) -> usize {
+    let hash = ((bytes[position] as usize) << 10)
+        ^ ((bytes[position + 1] as usize) << 5)
+        ^ (bytes[position + 2] as usize);
+    return hash & ((1 << HASH_BITS) - 1);
+}
+
+/// Last position of each hash of 3 bytes, and previous position with same hash of each position
+struct HashChains {
+    head: Vec<usize>,
+    prev: Vec<usize>,
+}
+
+impl HashChains {
+    fn insert(&mut self, bytes: &[u8], position: usize) {
+        if position + MIN_MATCH <= bytes.len() {
+            let hash = hash3(bytes, position);
+            self.prev[position % WINDOW_SIZE] = self.head[hash];
+            self.head[hash] = position;
+        }
+    }
+}
+
+/// Deflate bytes in a single fixed Huffman block, with matches found by hash chains
+fn deflate_fixed(bytes: &[u8]) -> Vec<u8> {
+    let mut writer = BitWriter::default();
+    writer.write_bits(1, 1);
+    writer.write_bits(1, 2);
+
+    let mut chains = HashChains {
+        head: vec![usize::MAX; 1 << HASH_BITS],
+        prev: vec![usize::MAX; WINDOW_SIZE],
+    };
+
+    let mut position = 0;
+    while position < bytes.len() {
+        let (mut best_length, mut best_distance) = (0, 0);
+        if position + MIN_MATCH <= bytes.len() {
+            let max_length = MAX_MATCH.min(bytes.len() - position);
+            let mut candidate = chains.head[hash3(bytes, position)];
+            let mut n_compared = 0;
+            while candidate != usize::MAX
+                && position - candidate <= WINDOW_SIZE
+                && n_compared < MAX_CHAIN
+            {
+                let mut length = 0;
+                while length < max_length && bytes[candidate + length] == bytes[position + length] {
+                    length += 1;
+                }
+                if length > best_length {
+                    (best_length, best_distance) = (length, position - candidate);
+                    if length == max_length {
+                        break;
+                    }
+                }
+                candidate = chains.prev[candidate % WINDOW_SIZE];
+                n_compared += 1;
+            }
+        }
+
+        if best_length < MIN_MATCH {
+            let (code, length) = fixed_literal_code(bytes[position] as u32);
+            writer.write_code(code, length);
+            chains.insert(bytes, position);
+            position += 1;
+            continue;
+        }
+        let length_idx = base_index(&LENGTH_BASE, best_length);
+        let (code, length) = fixed_literal_code(257 + length_idx as u32);
+        writer.write_code(code, length);
+        writer.write_bits(
+            (best_length - LENGTH_BASE[length_idx] as usize) as u32,
+            LENGTH_EXTRA[length_idx] as u32,
+        );
+        let distance_idx = base_index(&DIST_BASE, best_distance);
+        writer.write_code(distance_idx as u32, 5);
+        writer.write_bits(
+            (best_distance - DIST_BASE[distance_idx] as usize) as u32,
+            DIST_EXTRA[distance_idx] as u32,
+        );
+        for matched in position..position + best_length {
+            chains.insert(bytes, matched);
+        }
+        position += best_length;
+    }
+
+    let (code, length) = fixed_literal_code(256);
+    writer.write_code(code, length);
+    writer.align_to_byte();
+    return writer.bytes;
+}
+
+/// Deflate bytes without compression, in stored blocks
+fn deflate_stored(bytes: &[u8]) -> Vec<u8> {
+    let mut writer = BitWriter::default();
+    let n_blocks = bytes.len().div_ceil(MAX_STORED).max(1);
+    for block_idx in 0..n_blocks {
+        let start = block_idx * MAX_STORED;
+        let block = &bytes[start..bytes.len().min(start + MAX_STORED)];
+        writer.write_bits((block_idx + 1 == n_blocks) as u32, 1);
+        writer.write_bits(0, 2);
+        writer.align_to_byte();
+        writer.bytes.extend((block.len() as u16).to_le_bytes());
+        writer.bytes.extend((!(block.len() as u16)).to_le_bytes());
+        writer.bytes.extend(block);
+    }
+    return writer.bytes;
+}
+
+/// Compress bytes as zlib stream, keeping them stored when they don't compress
+fn zlib_compress(bytes: &[u8]) -> Vec<u8> {
+    let mut deflated = deflate_fixed(bytes);
+    let stored_size = bytes.len() + 5 * bytes.len().div_ceil(MAX_STORED).max(1);
+    if deflated.len() > stored_size {
+        deflated = deflate_stored(bytes);
+    }
+    let mut compressed: Vec<u8> = ZLIB_HEADER.to_vec();
+    compressed.extend(deflated);
+    compressed.extend(adler32(bytes).to_be_bytes());
+    return compressed;
+}
+
+/// Reader of bits, starting from the least significant bit of each byte
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    bit_buffer: u32,
+    n_bits: u32,
+}
+
+impl BitReader<'_> {
+    fn read_bits(&mut self, n: u32) -> Result<u32, Box<dyn Error>> {
+        while self.n_bits < n {
+            let byte = *self
+                .bytes
+                .get(self.position)
+                .ok_or("Unexpected end of deflate stream")?;
+            self.position += 1;
+            self.bit_buffer |= (byte as u32) << self.n_bits;
+            self.n_bits += 8;
+        }
+        let value = self.bit_buffer & ((1u32 << n) - 1);
+        self.bit_buffer >>= n;
+        self.n_bits -= n;
+        return Ok(value);
+    }
+
+    /// Skip remaining bits of current byte
+    fn align_to_byte(&mut self) {
+        self.bit_buffer = 0;
+        self.n_bits = 0;
+    }
+
+    fn read_bytes(&mut self, n: usize) -> Result<&[u8], Box<dyn Error>> {
+        let end = self
+            .position
+            .checked_add(n)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or("Unexpected end of deflate stream")?;
+        let bytes = &self.bytes[self.position..end];
+        self.position = end;
+        return Ok(bytes);
+    }
+
+    /// Decode symbol reading its code bit by bit, as codes of each length are consecutive
+    fn read_symbol(&mut self, huffman: &Huffman) -> Result<u16, Box<dyn Error>> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for length in 1..16 {
+            code |= self.read_bits(1)? as i32;
+            let count = huffman.counts[length] as i32;
+            if code - first < count {
+                return Ok(huffman.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        return Err("Invalid Huffman code in deflate stream".into());
+    }
+}
+
+/// Canonical Huffman code, as number of codes of each length and symbols sorted by code
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Result<Huffman, Box<dyn Error>> {
+        let mut counts = [0u16; 16];
+        for length in lengths.iter() {
+            counts[*length as usize] += 1;
+        }
+        // Incomplete codes are allowed, as for a single distance code
+        let mut left = 1i32;
+        for count in counts.iter().skip(1) {
+            left = (left << 1) - *count as i32;
+            if left < 0 {
+                return Err("Over-subscribed Huffman code lengths in deflate stream".into());
+            }
+        }
+        let mut symbols: Vec<u16> = Vec::with_capacity(lengths.len());
+        for length in 1..16u8 {
+            for (symbol, symbol_length) in lengths.iter().enumerate() {
+                if *symbol_length == length {
+                    symbols.push(symbol as u16);
+                }
+            }
+        }
+        return Ok(Huffman { counts, symbols });
+    }
+
+    fn fixed() -> (Huffman, Huffman) {
+        let mut lengths = [8u8; 288];
+        lengths[144..256].fill(9);
+        lengths[256..280].fill(7);
+        return (
+            Huffman::new(&lengths).unwrap(),
+            Huffman::new(&[5u8; 30]).unwrap(),
+        );
+    }
+}
+
+/// Literal/length and distance codes of dynamic block, saved as code lengths
+fn read_dynamic_huffman(reader: &mut BitReader) -> Result<(Huffman, Huffman), Box<dyn Error>> {
+    let n_literals = reader.read_bits(5)? as usize + 257;
+    let n_distances = reader.read_bits(5)? as usize + 1;
+    let n_code_lengths = reader.read_bits(4)? as usize + 4;
+    if n_literals > 286 || n_distances > 30 {
+        return Err("Too many codes in deflate dynamic block".into());
+    }
+    let mut code_lengths = [0u8; 19];
+    for symbol in CODE_LENGTHS_ORDER.iter().take(n_code_lengths) {
+        code_lengths[*symbol] = reader.read_bits(3)? as u8;
+    }
+    let code_lengths_huffman = Huffman::new(&code_lengths)?;
+
+    let mut lengths: Vec<u8> = Vec::with_capacity(n_literals + n_distances);
+    while lengths.len() < n_literals + n_distances {
+        let (value, repeat) = match reader.read_symbol(&code_lengths_huffman)? {
+            symbol @ 0..=15 => (symbol as u8, 1),
+            16 => {
+                let previous = *lengths
+                    .last()
+                    .ok_or("Repeated code length without previous one in deflate stream")?;
+                (previous, 3 + reader.read_bits(2)? as usize)
+            }
+            17 => (0, 3 + reader.read_bits(3)? as usize),
+            _ => (0, 11 + reader.read_bits(7)? as usize),
+        };
+        if lengths.len() + repeat > n_literals + n_distances {
+            return Err("Too many code lengths in deflate dynamic block".into());
+        }
+        lengths.extend(std::iter::repeat_n(value, repeat));
+    }
+    if lengths[256] == 0 {
+        return Err("Missing end of block code in deflate dynamic block".into());
+    }
+    return Ok((
+        Huffman::new(&lengths[..n_literals])?,
+        Huffman::new(&lengths[n_literals..])?,
+    ));
+}
+
+/// Decode symbols of compressed block until its end
+fn inflate_block(
+    reader: &mut BitReader,
+    literals: &Huffman,
+    distances: &Huffman,
+    decompressed: &mut Vec<u8>,
+) -> Result<(), Box<dyn Error>> {
+    loop {
+        let symbol = reader.read_symbol(literals)? as usize;
+        if symbol < 256 {
+            decompressed.push(symbol as u8);
+            continue;
+        }
+        if symbol == 256 {
+            return Ok(());
+        }
+        let length_idx = symbol - 257;
+        if length_idx >= LENGTH_BASE.len() {
+            return Err(format!("Invalid length symbol {} in deflate stream", symbol).into());
+        }
+        let length = LENGTH_BASE[length_idx] as usize
+            + reader.read_bits(LENGTH_EXTRA[length_idx] as u32)? as usize;
+        let distance_idx = reader.read_symbol(distances)? as usize;
+        if distance_idx >= DIST_BASE.len() {
+            return Err(
+                format!("Invalid distance symbol {} in deflate stream", distance_idx).into(),
+            );
+        }
+        let distance = DIST_BASE[distance_idx] as usize
+            + reader.read_bits(DIST_EXTRA[distance_idx] as u32)? as usize;
+        if distance > decompressed.len() {
+            return Err("Distance beyond start of data in deflate stream".into());
+        }
+        // Byte by byte, as the copy may overlap the bytes being written
+        let start = decompressed.len() - distance;
+        for i in 0..length {
+            decompressed.push(decompressed[start + i]);
+        }
+    }
+}
+
+/// Decompress zlib stream, with stored, fixed or dynamic Huffman blocks
+fn zlib_decompress(bytes: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    if bytes.len() < 6 {
+        return Err("Invalid zlib stream, too short".into());
+    }
+    let (cmf, flg) = (bytes[0], bytes[1]);
+    if cmf & 0x0F != 8 || cmf >> 4 > 7 || !(((cmf as u16) << 8) | flg as u16).is_multiple_of(31) {
+        return Err("Invalid zlib header".into());
+    }
+    if flg & 0x20 != 0 {
+        return Err("zlib streams with preset dictionary are not supported".into());
+    }
+
+    let mut reader = BitReader {
+        bytes: &bytes[..bytes.len() - 4],
+        position: 2,
+        bit_buffer: 0,
+        n_bits: 0,
+    };
+    let mut decompressed: Vec<u8> = Vec::new();
+    loop {
+        let is_final = reader.read_bits(1)? == 1;
+        match reader.read_bits(2)? {
+            0 => {
+                reader.align_to_byte();
+                let header = reader.read_bytes(4)?;
+                let length = u16::from_le_bytes([header[0], header[1]]);
+                if length != !u16::from_le_bytes([header[2], header[3]]) {
+                    return Err("Invalid stored block length in deflate stream".into());
+                }
+                decompressed.extend(reader.read_bytes(length as usize)?);
+            }
+            1 => {
+                let (literals, distances) = Huffman::fixed();
+                inflate_block(&mut reader, &literals, &distances, &mut decompressed)?;
+            }
+            2 => {
+                let (literals, distances) = read_dynamic_huffman(&mut reader)?;
+                inflate_block(&mut reader, &literals, &distances, &mut decompressed)?;
+            }
+            _ => return Err("Invalid deflate block type".into()),
+        }
+        if is_final {
+            break;
+        }
+    }
+    if reader.position != reader.bytes.len() {
+        return Err("Unexpected bytes after end of deflate stream".into());
+    }
+    let checksum = &bytes[bytes.len() - 4..];
+    if adler32(&decompressed).to_be_bytes() != checksum {
+        return Err("Invalid zlib checksum".into());
+    }
+    return Ok(decompressed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_zlib_roundtrip() {
+        let bytes: Vec<u8> = (0..10000u32).flat_map(|v| (v / 7).to_le_bytes()).collect();
+        let compression = Compression::from_name("zlib").unwrap();
+        let compressed = compression.compress(&bytes).unwrap();
+        assert!(compressed.len() < bytes.len() / 4);
+        assert_eq!(compression.decompress(&compressed).unwrap(), bytes);
+        assert!(compression.decompress(&bytes).is_err());
+        assert!(Compression::from_name("lzma").is_err());
+    }
+
+    #[test]
+    fn check_zlib_stored() {
+        // Pseudo random bytes don't compress, so they are stored in blocks
+        let mut state = 12345u32;
+        let bytes: Vec<u8> = (0..100000)
+            .map(|_| {
+                state = state.wrapping_mul(1103515245).wrapping_add(12345);
+                (state >> 16) as u8
+            })
+            .collect();
+        let compression = Compression::Zlib;
+        for data in [&bytes[..], &[]] {
+            let compressed = compression.compress(data).unwrap();
+            assert!(compressed.len() <= data.len() + 5 * 2 + 6);
+            assert_eq!(compression.decompress(&compressed).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn check_zlib_other_writers() {
+        // Streams with stored, fixed and dynamic blocks, written by another zlib implementation
+        let nodes = b"lagrangian nodes".to_vec();
+        let dynamic_data = b"v0 0\nv1 3\nv2 6\nv3 2\nv4 5\nv5 1\nv6 4\n".to_vec();
+        for (hex, data) in [
+            (
+                "7801011000efff6c616772616e6769616e206e6f64657335fd064e",
+                &nodes,
+            ),
+            ("78dacb494c2f4acc4bcf4ccc53c8cb4f492d060035fd064e", &nodes),
+            (
+                "78da05c1370100000cc3b03f2802a11b9af157221c22dda27ca25d62bc629de23c7a8650072b",
+                &dynamic_data,
+            ),
+        ] {
+            let compressed: Vec<u8> = (0..hex.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+                .collect();
+            assert_eq!(&Compression::Zlib.decompress(&compressed).unwrap(), data);
+
+            // Corrupted checksum
+            let mut corrupted = compressed.clone();
+            *corrupted.last_mut().unwrap() ^= 1;
+            assert!(Compression::Zlib.decompress(&corrupted).is_err());
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use clap::ValueEnum;
+use std::error::Error;
+use std::fmt;
+
+/// Encoding of indices arrays (`geometry.triangles` and surfaces `triangles_idxs`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum IndicesEncoding {
+    /// Difference to previous index, zigzag mapped to unsigned and written as LEB128 varint
+    DeltaZigzagVarint,
+}
+
+impl IndicesEncoding {
+    /// Name saved in LNAS `indices_encoding` field
+    pub fn name(&self) -> &'static str {
+        return match self {
+            IndicesEncoding::DeltaZigzagVarint => "delta-zigzag-varint",
+        };
+    }
+
+    pub fn from_name(name: &str) -> Result<IndicesEncoding, Box<dyn Error>> {
+        return match name {
+            "delta-zigzag-varint" => Ok(IndicesEncoding::DeltaZigzagVarint),
+            _ => Err(format!("Unknown indices encoding '{}'", name).into()),
+        };
+    }
+
+    pub fn encode(&self, indices: &Vec<u32>) -> Vec<u8> {
+        return match self {
+            IndicesEncoding::DeltaZigzagVarint => {
+                let mut bytes: Vec<u8> = Vec::with_capacity(indices.len());
+                let mut previous = 0i64;
+                for idx in indices.iter() {
+                    let delta = *idx as i64 - previous;
+                    write_varint(&mut bytes, zigzag(delta));
+                    previous = *idx as i64;
+                }
+                bytes
+            }
+        };
+    }
+
+    pub fn decode(&self, bytes: &[u8]) -> Result<Vec<u32>, Box<dyn Error>> {
+        return match self {
+            IndicesEncoding::DeltaZigzagVarint => {
+                let mut indices: Vec<u32> = Vec::new();
+                let mut previous = 0i64;
+                let mut position = 0;
+                while position < bytes.len() {
+                    let value = read_varint(bytes, &mut position)?;
+                    let idx = previous
+                        .checked_add(unzigzag(value))
+                        .filter(|idx| *idx >= 0 && *idx <= u32::MAX as i64)
+                        .ok_or("Decoded index out of u32 range")?;
+                    indices.push(idx as u32);
+                    previous = idx;
+                }
+                Ok(indices)
+            }
+        };
+    }
+
+    /// Number of indices in encoded bytes, without decoding them
+    pub fn count(&self, bytes: &[u8]) -> usize {
+        return match self {
+            // Last byte of each varint has most significant bit unset
+            IndicesEncoding::DeltaZigzagVarint => bytes.iter().filter(|b| **b & 0x80 == 0).count(),
+        };
+    }
+}
+
+/// Map signed to unsigned, so values close to zero have few bytes (0, -1, 1, -2 -> 0, 1, 2, 3)
+fn zigzag(value: i64) -> u64 {
+    return ((value << 1) ^ (value >> 63)) as u64;
+}
+
+fn unzigzag(value: u64) -> i64 {
+    return (value >> 1) as i64 ^ -((value & 1) as i64);
+}
+
+/// Write value as LEB128, 7 bits per byte with most significant bit set when more bytes follow
+fn write_varint(bytes: &mut Vec<u8>, value: u64) {
+    let mut value = value;
+    while value >= 0x80 {
+        bytes.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+fn read_varint(bytes: &[u8], position: &mut usize) -> Result<u64, Box<dyn Error>> {
+    let mut value = 0u64;
+    let mut shift = 0;
+    loop {
+        if *position >= bytes.len() {
+            return Err("Unexpected end of varint".into());
+        }
+        if shift > 63 {
+            return Err("Varint is too long".into());
+        }
+        let byte = bytes[*position];
+        *position += 1;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+        shift += 7;
+    }
+}
+
+/// Size of LNAS indices arrays before and after encoding, in bytes
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct EncodingReport {
+    pub raw_size: usize,
+    pub encoded_size: usize,
+}
+
+impl fmt::Display for EncodingReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let saved = 1f32 - self.encoded_size as f32 / (self.raw_size.max(1) as f32);
+        return write!(
+            f,
+            "Encoded indices from {} to {} bytes ({:.1}% saved)",
+            self.raw_size,
+            self.encoded_size,
+            100f32 * saved
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_delta_zigzag_varint() {
+        let encoding = IndicesEncoding::from_name("delta-zigzag-varint").unwrap();
+        // Deltas 0, 1, -1, 63 and -63 take one byte, 100000 takes three
+        let indices: Vec<u32> = vec![0, 1, 0, 63, 0, 100000];
+        let bytes = encoding.encode(&indices);
+        assert_eq!(bytes[..5], [0, 2, 1, 126, 125]);
+        assert_eq!(bytes.len(), 8);
+        assert_eq!(encoding.decode(&bytes).unwrap(), indices);
+        assert_eq!(encoding.count(&bytes), indices.len());
+
+        let extremes: Vec<u32> = vec![u32::MAX, 0, u32::MAX];
+        assert_eq!(
+            encoding.decode(&encoding.encode(&extremes)).unwrap(),
+            extremes
+        );
+
+        // Truncated varint and negative index
+        assert!(encoding.decode(&[0x80]).is_err());
+        assert!(encoding.decode(&[1]).is_err());
+        // Difference overflowing i64, after a valid index
+        let mut overflow: Vec<u8> = encoding.encode(&vec![u32::MAX]);
+        write_varint(&mut overflow, zigzag(i64::MAX));
+        assert!(encoding.decode(&overflow).is_err());
+        assert!(IndicesEncoding::from_name("delta").is_err());
+    }
+}
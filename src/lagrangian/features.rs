@@ -0,0 +1,121 @@
+use crate::lagrangian::mesh::{edge_key, get_edges_triangles, triangle_normal, Edge};
+use crate::utils::{Vec3f, Vec3u};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// Sharp edges and corners of mesh, which mesh modifications must preserve
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct FeatureEdges {
+    /// Feature edges, sorted
+    pub edges: Vec<Edge>,
+    /// Vertices where feature edges meet or end, sorted
+    pub corners: Vec<u32>,
+}
+
+impl FeatureEdges {
+    pub fn new(edges: HashSet<Edge>) -> FeatureEdges {
+        let mut edges: Vec<Edge> = edges.into_iter().collect();
+        edges.sort();
+
+        let mut vertices_n_edges: HashMap<u32, usize> = HashMap::new();
+        for (v0, v1) in edges.iter() {
+            *vertices_n_edges.entry(*v0).or_default() += 1;
+            *vertices_n_edges.entry(*v1).or_default() += 1;
+        }
+        // Vertices in the middle of a feature line have exactly two feature edges
+        let mut corners: Vec<u32> = vertices_n_edges
+            .into_iter()
+            .filter(|(_, n)| *n != 2)
+            .map(|(v, _)| v)
+            .collect();
+        corners.sort();
+        return FeatureEdges { edges, corners };
+    }
+
+    pub fn contains(&self, edge: &Edge) -> bool {
+        return self.edges.binary_search(edge).is_ok();
+    }
+
+    /// Features with each vertex `v` renumbered to `new_idxs[v]`
+    pub fn renumber_vertices(&self, new_idxs: &Vec<u32>) -> FeatureEdges {
+        let mut edges: Vec<Edge> = self
+            .edges
+            .iter()
+            .map(|(v0, v1)| edge_key(new_idxs[*v0 as usize], new_idxs[*v1 as usize]))
+            .collect();
+        edges.sort();
+        let mut corners: Vec<u32> = self.corners.iter().map(|v| new_idxs[*v as usize]).collect();
+        corners.sort();
+        return FeatureEdges { edges, corners };
+    }
+}
+
+impl fmt::Display for FeatureEdges {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(
+            f,
+            "Found {} feature edges and {} corners",
+            self.edges.len(),
+            self.corners.len()
+        );
+    }
+}
+
+/// Detect edges where the angle between neighbour triangles normals is above `feature_angle`
+/// (in degrees).
+///
+/// Boundary and non-manifold edges are also considered features.
+pub fn detect_feature_edges(
+    vertices: &Vec<Vec3f>,
+    triangles: &Vec<Vec3u>,
+    feature_angle: f32,
+) -> FeatureEdges {
+    let min_cos = feature_angle.to_radians().cos();
+    let mut edges: HashSet<Edge> = HashSet::new();
+    for (edge, edge_triangles) in get_edges_triangles(triangles).into_iter() {
+        if edge_triangles.len() != 2 {
+            edges.insert(edge);
+            continue;
+        }
+        let n0 = triangle_normal(vertices, &triangles[edge_triangles[0]]);
+        let n1 = triangle_normal(vertices, &triangles[edge_triangles[1]]);
+        if n0.dot(n1) < min_cos {
+            edges.insert(edge);
+        }
+    }
+    return FeatureEdges::new(edges);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lagrangian::test_utils::get_joined_stl;
+
+    #[test]
+    fn check_features_stl_cube() {
+        let (vertices, triangles) = get_joined_stl("cube");
+        let features = detect_feature_edges(&vertices, &triangles, 30f32);
+        // Faces diagonals are not features
+        assert_eq!(features.edges.len(), 12);
+        assert_eq!(features.corners.len(), 8);
+    }
+
+    #[test]
+    fn check_features_stl_sphere() {
+        let (vertices, triangles) = get_joined_stl("sphere");
+        let features = detect_feature_edges(&vertices, &triangles, 45f32);
+        assert_eq!(features.edges.len(), 0);
+        assert_eq!(features.corners.len(), 0);
+    }
+
+    #[test]
+    fn check_features_stl_plane_boundary() {
+        let (vertices, triangles) = get_joined_stl("plane");
+        let features = detect_feature_edges(&vertices, &triangles, 45f32);
+        assert!(features.edges.len() > 0);
+        // Boundary is a closed loop
+        for (v0, v1) in features.edges.iter() {
+            assert!(!features.corners.contains(v0) || !features.corners.contains(v1));
+        }
+    }
+}
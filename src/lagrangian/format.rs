@@ -1,20 +1,504 @@
+use crate::lagrangian::attributes::SurfaceAttributes;
+use crate::lagrangian::compression::{Compression, CompressionReport};
+use crate::lagrangian::encoding::{EncodingReport, IndicesEncoding};
+use crate::lagrangian::features::FeatureEdges;
+use crate::lagrangian::metadata::{get_bounding_box_metadata, MetadataLNAS};
+use crate::lagrangian::quantization::{Quantization, QuantizationDtype, QuantizationReport};
 use crate::utils::{Vec3f, Vec3u};
 use base64::{engine::general_purpose, Engine as _};
 
-use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, string::String};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::string::String;
+
+/// Array of little-endian values (compressed or encoded if LNAS is), base64 encoded in YAML
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct ArrayLNAS(pub Vec<u8>);
+
+impl Serialize for ArrayLNAS {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        return serializer.serialize_str(&encode_bytes(&self.0));
+    }
+}
+
+impl<'de> Deserialize<'de> for ArrayLNAS {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<ArrayLNAS, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        return decode_bytes(&encoded)
+            .map(ArrayLNAS)
+            .map_err(de::Error::custom);
+    }
+}
 
 #[derive(PartialEq, Serialize, Deserialize)]
 pub struct GeometryLNAS {
-    pub vertices: String,
-    pub triangles: String,
+    pub vertices: ArrayLNAS,
+    /// Quantization of vertices, which are f32 when absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vertices_quantization: Option<Quantization>,
+    /// Shift subtracted from vertices, original positions are `vertices + origin_shift`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin_shift: Option<[f64; 3]>,
+    pub triangles: ArrayLNAS,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vertices_normals: Option<ArrayLNAS>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vertices_areas: Option<ArrayLNAS>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub triangles_centroids: Option<ArrayLNAS>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub triangles_areas: Option<ArrayLNAS>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub triangles_normals: Option<ArrayLNAS>,
+}
+
+/// Surface, as indexes of its triangles and optional attributes
+#[derive(PartialEq, Debug, Serialize, Deserialize)]
+#[serde(from = "SurfaceLayout")]
+pub struct SurfaceLNAS {
+    pub triangles_idxs: ArrayLNAS,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attributes: Option<SurfaceAttributes>,
+}
+
+/// Surface layouts accepted when reading
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SurfaceLayout {
+    /// Up to v0.5, surface name mapped directly to its triangles indexes
+    Flat(ArrayLNAS),
+    Nested {
+        triangles_idxs: ArrayLNAS,
+        #[serde(default)]
+        attributes: Option<SurfaceAttributes>,
+    },
+}
+
+impl From<SurfaceLayout> for SurfaceLNAS {
+    fn from(layout: SurfaceLayout) -> SurfaceLNAS {
+        return match layout {
+            SurfaceLayout::Flat(triangles_idxs) => SurfaceLNAS {
+                triangles_idxs,
+                attributes: None,
+            },
+            SurfaceLayout::Nested {
+                triangles_idxs,
+                attributes,
+            } => SurfaceLNAS {
+                triangles_idxs,
+                attributes,
+            },
+        };
+    }
+}
+
+#[derive(PartialEq, Serialize, Deserialize)]
+pub struct FeaturesLNAS {
+    pub edges: ArrayLNAS,
+    pub corners: ArrayLNAS,
 }
 
 #[derive(PartialEq, Serialize, Deserialize)]
 pub struct LNAS {
     pub version: String,
+    /// Compression of all arrays before base64 encoding
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compression: Option<String>,
+    /// Encoding of triangles and surfaces indices, applied before compression
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub indices_encoding: Option<String>,
+    /// Provenance of file, saved with `--metadata`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<MetadataLNAS>,
     pub geometry: GeometryLNAS,
-    pub surfaces: HashMap<String, String>,
+    pub surfaces: HashMap<String, SurfaceLNAS>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub features: Option<FeaturesLNAS>,
+}
+
+impl LNAS {
+    /// Add metadata, filling bounding box and counts from geometry
+    pub fn set_metadata(&mut self, metadata: MetadataLNAS) -> Result<(), Box<dyn Error>> {
+        let mut metadata = metadata;
+        let vertices = self.get_vertices()?;
+        metadata.bounding_box = get_bounding_box_metadata(&vertices, self.geometry.origin_shift);
+        metadata.n_vertices = vertices.len();
+        metadata.n_triangles = self.get_triangles()?.len();
+        metadata.n_surfaces = self.surfaces.len();
+        self.metadata = Some(metadata);
+        return Ok(());
+    }
+
+    /// Add attributes to surfaces, ignoring the ones not found
+    pub fn set_surfaces_attributes(&mut self, attributes: BTreeMap<String, SurfaceAttributes>) {
+        for (surface_name, surface_attributes) in attributes.into_iter() {
+            if let Some(surface) = self.surfaces.get_mut(&surface_name) {
+                surface.attributes = Some(surface_attributes);
+            }
+        }
+    }
+
+    /// Attributes of surface, if it has any
+    pub fn get_surface_attributes(&self, surface_name: &str) -> Option<&SurfaceAttributes> {
+        return self.surfaces.get(surface_name)?.attributes.as_ref();
+    }
+
+    /// Attributes of all surfaces that have them
+    pub fn get_surfaces_attributes(&self) -> BTreeMap<String, SurfaceAttributes> {
+        let mut attributes: BTreeMap<String, SurfaceAttributes> = BTreeMap::new();
+        for (surface_name, surface) in self.surfaces.iter() {
+            if let Some(surface_attributes) = &surface.attributes {
+                attributes.insert(surface_name.to_owned(), surface_attributes.clone());
+            }
+        }
+        return attributes;
+    }
+
+    /// Record shift subtracted from vertices
+    pub fn set_origin_shift(&mut self, shift: &[f64; 3]) {
+        self.geometry.origin_shift = Some(*shift);
+    }
+
+    /// Add unit normal of each vertex to geometry
+    pub fn set_vertices_normals(&mut self, normals: &Vec<Vec3f>) {
+        self.geometry.vertices_normals = Some(encode_vec3f(normals));
+    }
+
+    /// Add area of each vertex to geometry
+    pub fn set_vertices_areas(&mut self, areas: &Vec<f32>) {
+        self.geometry.vertices_areas = Some(encode_f32(areas));
+    }
+
+    /// Add centroid, area and unit normal of each triangle to geometry
+    pub fn set_triangles_properties(
+        &mut self,
+        centroids: &Vec<Vec3f>,
+        areas: &Vec<f32>,
+        normals: &Vec<Vec3f>,
+    ) {
+        self.geometry.triangles_centroids = Some(encode_vec3f(centroids));
+        self.geometry.triangles_areas = Some(encode_f32(areas));
+        self.geometry.triangles_normals = Some(encode_vec3f(normals));
+    }
+
+    /// Add feature edges (as vertices indexes pairs) and corners
+    pub fn set_features(&mut self, features: &FeatureEdges) {
+        let edges: Vec<u32> = features
+            .edges
+            .iter()
+            .flat_map(|(v0, v1)| [*v0, *v1])
+            .collect();
+        self.features = Some(FeaturesLNAS {
+            edges: encode_u32(&edges),
+            corners: encode_u32(&features.corners),
+        });
+    }
+
+    /// All arrays
+    fn arrays_mut(&mut self) -> Vec<&mut ArrayLNAS> {
+        let geometry = &mut self.geometry;
+        let mut arrays: Vec<&mut ArrayLNAS> = vec![&mut geometry.vertices, &mut geometry.triangles];
+        arrays.extend(
+            [
+                &mut geometry.vertices_normals,
+                &mut geometry.vertices_areas,
+                &mut geometry.triangles_centroids,
+                &mut geometry.triangles_areas,
+                &mut geometry.triangles_normals,
+            ]
+            .into_iter()
+            .flatten(),
+        );
+        arrays.extend(self.surfaces.values_mut().map(|s| &mut s.triangles_idxs));
+        if let Some(features) = &mut self.features {
+            arrays.push(&mut features.edges);
+            arrays.push(&mut features.corners);
+        }
+        return arrays;
+    }
+
+    pub fn get_compression(&self) -> Result<Option<Compression>, Box<dyn Error>> {
+        return match &self.compression {
+            Some(name) => Ok(Some(Compression::from_name(name)?)),
+            None => Ok(None),
+        };
+    }
+
+    /// Compress all arrays, reporting their sizes before and after it
+    pub fn compress(
+        &mut self,
+        compression: Compression,
+    ) -> Result<CompressionReport, Box<dyn Error>> {
+        if self.compression.is_some() {
+            return Err("LNAS arrays are already compressed".into());
+        }
+        let mut report = CompressionReport::default();
+        for array in self.arrays_mut().into_iter() {
+            let compressed = compression.compress(&array.0)?;
+            report.raw_size += array.0.len();
+            report.compressed_size += compressed.len();
+            array.0 = compressed;
+        }
+        self.compression = Some(compression.name().to_string());
+        return Ok(report);
+    }
+
+    /// Decompress all arrays, if they are compressed
+    pub fn decompress(&mut self) -> Result<(), Box<dyn Error>> {
+        if let Some(compression) = self.get_compression()? {
+            for array in self.arrays_mut().into_iter() {
+                array.0 = compression.decompress(&array.0)?;
+            }
+        }
+        self.compression = None;
+        return Ok(());
+    }
+
+    /// Quantize vertices relative to their bounding box, reporting the maximum coordinate error.
+    ///
+    /// Must be applied before compression
+    pub fn quantize_vertices(
+        &mut self,
+        dtype: QuantizationDtype,
+    ) -> Result<QuantizationReport, Box<dyn Error>> {
+        if self.geometry.vertices_quantization.is_some() {
+            return Err("LNAS vertices are already quantized".into());
+        }
+        if self.compression.is_some() {
+            return Err("LNAS vertices must be quantized before compression".into());
+        }
+        let vertices = bytes_to_vec3f(&self.geometry.vertices.0)?;
+        let quantization = Quantization::from_vertices(&vertices, dtype);
+        let quantized = quantization.quantize(&vertices);
+        let dequantized = quantization.dequantize(&quantized)?;
+        let mut max_error = 0f32;
+        for (v0, v1) in vertices.iter().zip(dequantized.iter()) {
+            let diff = *v0 - *v1;
+            max_error = max_error.max(diff.x.abs().max(diff.y.abs()).max(diff.z.abs()));
+        }
+
+        let report = QuantizationReport {
+            dtype,
+            raw_size: 12 * vertices.len(),
+            quantized_size: quantized.len(),
+            max_error,
+        };
+        self.geometry.vertices = ArrayLNAS(quantized);
+        self.geometry.vertices_quantization = Some(quantization);
+        return Ok(report);
+    }
+
+    /// Restore vertices to f32, if they are quantized
+    pub fn dequantize_vertices(&mut self) -> Result<(), Box<dyn Error>> {
+        if self.geometry.vertices_quantization.is_some() {
+            if self.compression.is_some() {
+                return Err("LNAS vertices must be dequantized after decompression".into());
+            }
+            self.geometry.vertices = encode_vec3f(&self.get_vertices()?);
+        }
+        self.geometry.vertices_quantization = None;
+        return Ok(());
+    }
+
+    pub fn get_indices_encoding(&self) -> Result<Option<IndicesEncoding>, Box<dyn Error>> {
+        return match &self.indices_encoding {
+            Some(name) => Ok(Some(IndicesEncoding::from_name(name)?)),
+            None => Ok(None),
+        };
+    }
+
+    /// Triangles and surfaces indices arrays
+    fn indices_arrays_mut(&mut self) -> Vec<&mut ArrayLNAS> {
+        let mut arrays: Vec<&mut ArrayLNAS> = vec![&mut self.geometry.triangles];
+        arrays.extend(self.surfaces.values_mut().map(|s| &mut s.triangles_idxs));
+        return arrays;
+    }
+
+    /// Encode triangles and surfaces indices, reporting their sizes before and after it.
+    ///
+    /// Must be applied before compression
+    pub fn encode_indices(
+        &mut self,
+        encoding: IndicesEncoding,
+    ) -> Result<EncodingReport, Box<dyn Error>> {
+        if self.indices_encoding.is_some() {
+            return Err("LNAS indices are already encoded".into());
+        }
+        if self.compression.is_some() {
+            return Err("LNAS indices must be encoded before compression".into());
+        }
+        let mut report = EncodingReport::default();
+        for array in self.indices_arrays_mut().into_iter() {
+            let encoded = encoding.encode(&bytes_to_u32(&array.0)?);
+            report.raw_size += array.0.len();
+            report.encoded_size += encoded.len();
+            array.0 = encoded;
+        }
+        self.indices_encoding = Some(encoding.name().to_string());
+        return Ok(report);
+    }
+
+    /// Decode triangles and surfaces indices to raw u32, if they are encoded
+    pub fn decode_indices(&mut self) -> Result<(), Box<dyn Error>> {
+        if let Some(encoding) = self.get_indices_encoding()? {
+            if self.compression.is_some() {
+                return Err("LNAS indices must be decoded after decompression".into());
+            }
+            for array in self.indices_arrays_mut().into_iter() {
+                *array = encode_u32(&encoding.decode(&array.0)?);
+            }
+        }
+        self.indices_encoding = None;
+        return Ok(());
+    }
+
+    /// Raw bytes of array, decompressed if needed
+    pub fn array_bytes(&self, array: &ArrayLNAS) -> Result<Vec<u8>, Box<dyn Error>> {
+        return match self.get_compression()? {
+            Some(compression) => compression.decompress(&array.0),
+            None => Ok(array.0.clone()),
+        };
+    }
+
+    /// Indices of triangles or surfaces array, decoded if needed
+    pub fn array_indices(&self, array: &ArrayLNAS) -> Result<Vec<u32>, Box<dyn Error>> {
+        let bytes = self.array_bytes(array)?;
+        return match self.get_indices_encoding()? {
+            Some(encoding) => encoding.decode(&bytes),
+            None => bytes_to_u32(&bytes),
+        };
+    }
+
+    /// Vertices positions, dequantized if needed
+    pub fn get_vertices(&self) -> Result<Vec<Vec3f>, Box<dyn Error>> {
+        let bytes = self.array_bytes(&self.geometry.vertices)?;
+        return match &self.geometry.vertices_quantization {
+            Some(quantization) => quantization.dequantize(&bytes),
+            None => bytes_to_vec3f(&bytes),
+        };
+    }
+
+    /// Number of vertices, without dequantizing them
+    pub fn n_vertices(&self) -> Result<usize, Box<dyn Error>> {
+        let vertex_size = match &self.geometry.vertices_quantization {
+            Some(quantization) => 3 * quantization.dtype.n_bytes(),
+            None => 12,
+        };
+        let n_bytes = match self.get_compression()? {
+            Some(compression) => compression.decompressed_size(&self.geometry.vertices.0)?,
+            None => self.geometry.vertices.0.len(),
+        };
+        return Ok(n_bytes / vertex_size);
+    }
+
+    /// Number of triangles, without decoding their indices
+    pub fn n_triangles(&self) -> Result<usize, Box<dyn Error>> {
+        let triangles = &self.geometry.triangles.0;
+        let n_indices = match (self.get_compression()?, self.get_indices_encoding()?) {
+            (None, None) => triangles.len() / 4,
+            (Some(compression), None) => compression.decompressed_size(triangles)? / 4,
+            (None, Some(encoding)) => encoding.count(triangles),
+            (Some(compression), Some(encoding)) => {
+                encoding.count(&compression.decompress(triangles)?)
+            }
+        };
+        return Ok(n_indices / 3);
+    }
+
+    pub fn get_triangles(&self) -> Result<Vec<Vec3u>, Box<dyn Error>> {
+        let indices = self.array_indices(&self.geometry.triangles)?;
+        if !indices.len().is_multiple_of(3) {
+            return Err(format!(
+                "Invalid number of triangles indices {}, expected a multiple of 3",
+                indices.len()
+            )
+            .into());
+        }
+        return Ok(indices
+            .chunks_exact(3)
+            .map(|t| Vec3u {
+                x: t[0],
+                y: t[1],
+                z: t[2],
+            })
+            .collect());
+    }
+
+    /// Triangles indexes of each surface
+    pub fn get_surfaces(&self) -> Result<HashMap<String, Vec<u32>>, Box<dyn Error>> {
+        let mut surfaces: HashMap<String, Vec<u32>> = HashMap::new();
+        for (surface_name, surface) in self.surfaces.iter() {
+            surfaces.insert(
+                surface_name.to_owned(),
+                self.array_indices(&surface.triangles_idxs)?,
+            );
+        }
+        return Ok(surfaces);
+    }
+}
+
+pub fn encode_bytes(bytes: &[u8]) -> String {
+    return general_purpose::STANDARD.encode(bytes);
+}
+
+pub fn decode_bytes(encoded: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    return Ok(general_purpose::STANDARD.decode(encoded)?);
+}
+
+/// Split bytes in chunks of `size`, failing if they are not a multiple of it
+fn bytes_chunks(
+    bytes: &[u8],
+    size: usize,
+) -> Result<std::slice::ChunksExact<'_, u8>, Box<dyn Error>> {
+    if !bytes.len().is_multiple_of(size) {
+        return Err(format!(
+            "Invalid number of bytes {}, expected a multiple of {}",
+            bytes.len(),
+            size
+        )
+        .into());
+    }
+    return Ok(bytes.chunks_exact(size));
+}
+
+pub fn bytes_to_vec3f(bytes: &[u8]) -> Result<Vec<Vec3f>, Box<dyn Error>> {
+    return Ok(bytes_chunks(bytes, 12)?
+        .map(|b| Vec3f::from_bytes_le(&b.to_vec()))
+        .collect());
+}
+
+pub fn bytes_to_vec3u(bytes: &[u8]) -> Result<Vec<Vec3u>, Box<dyn Error>> {
+    return Ok(bytes_chunks(bytes, 12)?
+        .map(|b| Vec3u::from_bytes_le(&b.to_vec()))
+        .collect());
+}
+
+pub fn bytes_to_u32(bytes: &[u8]) -> Result<Vec<u32>, Box<dyn Error>> {
+    return Ok(bytes_chunks(bytes, 4)?
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect());
+}
+
+pub fn bytes_to_f32(bytes: &[u8]) -> Result<Vec<f32>, Box<dyn Error>> {
+    return Ok(bytes_chunks(bytes, 4)?
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect());
+}
+
+pub fn encode_vec3f(values: &Vec<Vec3f>) -> ArrayLNAS {
+    let bytes: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes_as_f32()).collect();
+    return ArrayLNAS(bytes);
+}
+
+pub fn encode_u32(values: &Vec<u32>) -> ArrayLNAS {
+    let bytes: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+    return ArrayLNAS(bytes);
+}
+
+pub fn encode_f32(values: &Vec<f32>) -> ArrayLNAS {
+    let bytes: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+    return ArrayLNAS(bytes);
 }
 
 pub fn get_lnas_obj_save(
@@ -22,7 +506,7 @@ pub fn get_lnas_obj_save(
     joined_triangles: &Vec<Vec3u>,
     surfaces: &HashMap<String, Vec<u32>>,
 ) -> LNAS {
-    let version: String = String::from("v".to_owned() + env!("CARGO_PKG_VERSION"));
+    let version: String = "v".to_owned() + env!("CARGO_PKG_VERSION");
 
     let vertices_bytes: Vec<u8> = joined_vertices
         .iter()
@@ -33,26 +517,39 @@ pub fn get_lnas_obj_save(
         .flat_map(|v| v.to_le_bytes_as_u32())
         .collect();
 
-    let vertices_b64 = general_purpose::STANDARD.encode(vertices_bytes);
-    let triangles_b64 = general_purpose::STANDARD.encode(triangles_bytes);
-
-    let mut surfaces_save: HashMap<String, String> = HashMap::new();
+    let mut surfaces_save: HashMap<String, SurfaceLNAS> = HashMap::new();
     for (surface_name, triangles_idxs) in surfaces.iter() {
         let surface_bytes: Vec<u8> = triangles_idxs
             .iter()
             .flat_map(|v| v.to_le_bytes())
             .collect();
-        let surface_b64 = general_purpose::STANDARD.encode(surface_bytes);
-        surfaces_save.insert(surface_name.to_owned(), surface_b64);
+        surfaces_save.insert(
+            surface_name.to_owned(),
+            SurfaceLNAS {
+                triangles_idxs: ArrayLNAS(surface_bytes),
+                attributes: None,
+            },
+        );
     }
 
     let lnas_obj = LNAS {
-        version: version,
+        version,
+        compression: None,
+        indices_encoding: None,
+        metadata: None,
         geometry: GeometryLNAS {
-            vertices: vertices_b64,
-            triangles: triangles_b64,
+            vertices: ArrayLNAS(vertices_bytes),
+            vertices_quantization: None,
+            origin_shift: None,
+            triangles: ArrayLNAS(triangles_bytes),
+            vertices_normals: None,
+            vertices_areas: None,
+            triangles_centroids: None,
+            triangles_areas: None,
+            triangles_normals: None,
         },
         surfaces: surfaces_save,
+        features: None,
     };
     return lnas_obj;
 }
@@ -61,19 +558,15 @@ pub fn get_lnas_obj_save(
 mod tests {
     use super::*;
     use crate::lagrangian::format::get_lnas_obj_save;
-    use crate::lagrangian::join::join_information;
-    use crate::lagrangian::triangle::generate_lagrangian_triangles;
-    use crate::lagrangian::vertice::generate_lagrangian_vertices;
-    use crate::stl::surfaces::get_surfaces;
-    use std::path;
+    use crate::lagrangian::join::reorder_vertices;
+    use crate::lagrangian::nodes::{
+        get_vertices_areas, get_vertices_normals, AreaMethod, NormalWeighting,
+    };
+    use crate::lagrangian::test_utils::get_joined_stls;
 
     fn get_vecs_from_geometry(geometry: &GeometryLNAS) -> (Vec<Vec3f>, Vec<Vec3u>) {
-        let vertices_bytes = general_purpose::STANDARD
-            .decode(&geometry.vertices)
-            .unwrap();
-        let triangles_bytes = general_purpose::STANDARD
-            .decode(&geometry.triangles)
-            .unwrap();
+        let vertices_bytes = &geometry.vertices.0;
+        let triangles_bytes = &geometry.triangles.0;
 
         let mut vertices: Vec<Vec3f> = Vec::new();
         let mut triangles: Vec<Vec3u> = Vec::new();
@@ -126,17 +619,7 @@ mod tests {
 
     #[test]
     fn check_join_info_stl_cube() {
-        let mut files: HashMap<String, path::PathBuf> = HashMap::new();
-        let filename = "examples/stl/cube.stl".to_string();
-        files.insert(
-            "cube".to_string(),
-            path::Path::new(filename.as_str()).to_owned(),
-        );
-
-        let (triangles, surfaces) = get_surfaces(&files);
-        let lagr_vertices = generate_lagrangian_vertices(&triangles);
-        let lagr_triangles = generate_lagrangian_triangles(&lagr_vertices, &triangles);
-        let (joined_vertices, joined_triangles) = join_information(&lagr_vertices, &lagr_triangles);
+        let (joined_vertices, joined_triangles, surfaces) = get_joined_stls(&["cube"]);
         let lnas_obj = get_lnas_obj_save(&joined_vertices, &joined_triangles, &surfaces);
 
         check_lnas_geometry(&lnas_obj.geometry, &joined_vertices, &joined_triangles);
@@ -144,40 +627,145 @@ mod tests {
 
     #[test]
     fn check_join_info_stl_terrain() {
-        let mut files: HashMap<String, path::PathBuf> = HashMap::new();
-        let filename = "examples/stl/terrain.stl".to_string();
-        files.insert(
-            "terrain".to_string(),
-            path::Path::new(filename.as_str()).to_owned(),
-        );
-
-        let (triangles, surfaces) = get_surfaces(&files);
-        let lagr_vertices = generate_lagrangian_vertices(&triangles);
-        let lagr_triangles = generate_lagrangian_triangles(&lagr_vertices, &triangles);
-        let (joined_vertices, joined_triangles) = join_information(&lagr_vertices, &lagr_triangles);
+        let (joined_vertices, joined_triangles, surfaces) = get_joined_stls(&["terrain"]);
         let lnas_obj = get_lnas_obj_save(&joined_vertices, &joined_triangles, &surfaces);
 
         check_lnas_geometry(&lnas_obj.geometry, &joined_vertices, &joined_triangles);
     }
 
     #[test]
-    fn check_save_surfaces_combine() {
-        let mut files: HashMap<String, path::PathBuf> = HashMap::new();
-        let filename: String = "examples/stl/cube.stl".to_string();
-        files.insert(
-            "cube".to_string(),
-            path::Path::new(filename.as_str()).to_owned(),
+    fn check_vertices_properties() {
+        let (joined_vertices, joined_triangles, surfaces) = get_joined_stls(&["cube"]);
+        let mut lnas_obj = get_lnas_obj_save(&joined_vertices, &joined_triangles, &surfaces);
+        assert!(lnas_obj.geometry.vertices_normals.is_none());
+
+        let normals =
+            get_vertices_normals(&joined_vertices, &joined_triangles, NormalWeighting::Area);
+        let areas = get_vertices_areas(&joined_vertices, &joined_triangles, AreaMethod::Voronoi);
+        lnas_obj.set_vertices_normals(&normals);
+        lnas_obj.set_vertices_areas(&areas);
+
+        // Normals are encoded same as vertices
+        let normals_geometry = GeometryLNAS {
+            vertices: lnas_obj.geometry.vertices_normals.clone().unwrap(),
+            vertices_quantization: None,
+            origin_shift: None,
+            triangles: lnas_obj.geometry.triangles.clone(),
+            vertices_normals: None,
+            vertices_areas: None,
+            triangles_centroids: None,
+            triangles_areas: None,
+            triangles_normals: None,
+        };
+        check_lnas_geometry(&normals_geometry, &normals, &joined_triangles);
+        let areas_bytes = lnas_obj.geometry.vertices_areas.unwrap().0;
+        assert_eq!(areas_bytes.len(), 4 * joined_vertices.len());
+    }
+
+    #[test]
+    fn check_compression_roundtrip() {
+        let (joined_vertices, joined_triangles, surfaces) = get_joined_stls(&["terrain"]);
+        let mut lnas_obj = get_lnas_obj_save(&joined_vertices, &joined_triangles, &surfaces);
+        let raw_vertices = lnas_obj.geometry.vertices.clone();
+
+        let report = lnas_obj.compress(Compression::Zlib).unwrap();
+        assert!(report.compressed_size < report.raw_size);
+        assert!(lnas_obj.compress(Compression::Zlib).is_err());
+        assert_eq!(lnas_obj.get_vertices().unwrap(), joined_vertices);
+        assert_eq!(lnas_obj.get_surfaces().unwrap(), surfaces);
+
+        lnas_obj.decompress().unwrap();
+        assert!(lnas_obj.compression.is_none());
+        assert_eq!(lnas_obj.geometry.vertices, raw_vertices);
+    }
+
+    #[test]
+    fn check_indices_encoding_roundtrip() {
+        let (joined_vertices, joined_triangles, surfaces) = get_joined_stls(&["terrain"]);
+        let (joined_vertices, joined_triangles) =
+            reorder_vertices(&joined_vertices, &joined_triangles, None);
+        let mut lnas_obj = get_lnas_obj_save(&joined_vertices, &joined_triangles, &surfaces);
+        let raw_triangles = lnas_obj.geometry.triangles.clone();
+
+        let report = lnas_obj
+            .encode_indices(IndicesEncoding::DeltaZigzagVarint)
+            .unwrap();
+        // Reordered indices mostly take a single byte instead of four
+        assert!(report.encoded_size < report.raw_size / 2);
+        assert!(lnas_obj
+            .encode_indices(IndicesEncoding::DeltaZigzagVarint)
+            .is_err());
+        assert!(lnas_obj.get_triangles().unwrap() == joined_triangles);
+        assert_eq!(lnas_obj.get_surfaces().unwrap(), surfaces);
+
+        // Encoded indices are compressed and decompressed as other arrays
+        lnas_obj.compress(Compression::Zlib).unwrap();
+        assert!(lnas_obj.get_triangles().unwrap() == joined_triangles);
+        assert!(lnas_obj.decode_indices().is_err());
+        lnas_obj.decompress().unwrap();
+        lnas_obj.decode_indices().unwrap();
+        assert!(lnas_obj.indices_encoding.is_none());
+        assert_eq!(lnas_obj.geometry.triangles, raw_triangles);
+    }
+
+    #[test]
+    fn check_quantized_vertices() {
+        let (joined_vertices, joined_triangles, surfaces) = get_joined_stls(&["terrain"]);
+        let mut lnas_obj = get_lnas_obj_save(&joined_vertices, &joined_triangles, &surfaces);
+
+        let report = lnas_obj.quantize_vertices(QuantizationDtype::U16).unwrap();
+        assert_eq!(2 * report.quantized_size, report.raw_size);
+        assert!(lnas_obj.quantize_vertices(QuantizationDtype::U16).is_err());
+        let vertices = lnas_obj.get_vertices().unwrap();
+        let mut max_error = 0f32;
+        for (v0, v1) in joined_vertices.iter().zip(vertices.iter()) {
+            max_error = max_error.max((*v0 - *v1).norm());
+        }
+        assert!(max_error > 0f32);
+        // Error in each axis is within reported maximum
+        assert!(max_error <= 3f32.sqrt() * report.max_error);
+
+        // Quantization parameters are kept in YAML
+        let read_obj: LNAS =
+            serde_yaml::from_str(&serde_yaml::to_string(&lnas_obj).unwrap()).unwrap();
+        assert!(read_obj == lnas_obj);
+
+        lnas_obj.compress(Compression::Zlib).unwrap();
+        assert_eq!(lnas_obj.get_vertices().unwrap(), vertices);
+        assert!(lnas_obj.dequantize_vertices().is_err());
+        lnas_obj.decompress().unwrap();
+        lnas_obj.dequantize_vertices().unwrap();
+        assert!(lnas_obj.geometry.vertices_quantization.is_none());
+        assert_eq!(
+            bytes_to_vec3f(&lnas_obj.geometry.vertices.0).unwrap(),
+            vertices
         );
-        let filename: String = "examples/stl/plane.stl".to_string();
-        files.insert(
-            "plane".to_string(),
-            path::Path::new(filename.as_str()).to_owned(),
+    }
+
+    #[test]
+    fn check_surfaces_layouts() {
+        let (joined_vertices, joined_triangles, surfaces) = get_joined_stls(&["cube"]);
+        let lnas_obj = get_lnas_obj_save(&joined_vertices, &joined_triangles, &surfaces);
+
+        // Surfaces are saved nested, as documented
+        let mut value = serde_yaml::to_value(&lnas_obj).unwrap();
+        let cube = &value["surfaces"]["cube"];
+        assert_eq!(
+            cube["triangles_idxs"].as_str().unwrap(),
+            encode_bytes(&lnas_obj.surfaces.get("cube").unwrap().triangles_idxs.0)
         );
+        assert!(cube.get("attributes").is_none());
 
-        let (triangles, surfaces) = get_surfaces(&files);
-        let lagr_vertices = generate_lagrangian_vertices(&triangles);
-        let lagr_triangles = generate_lagrangian_triangles(&lagr_vertices, &triangles);
-        let (joined_vertices, joined_triangles) = join_information(&lagr_vertices, &lagr_triangles);
+        // Up to v0.5, surfaces were saved as name to triangles indexes
+        value["surfaces"]["cube"] = cube["triangles_idxs"].clone();
+        let flat_obj: LNAS = serde_yaml::from_value(value).unwrap();
+        assert!(flat_obj == lnas_obj);
+        assert_eq!(flat_obj.get_surfaces().unwrap(), surfaces);
+    }
+
+    #[test]
+    fn check_save_surfaces_combine() {
+        let (joined_vertices, joined_triangles, surfaces) = get_joined_stls(&["cube", "plane"]);
         let lnas_obj = get_lnas_obj_save(&joined_vertices, &joined_triangles, &surfaces);
 
         check_lnas_geometry(&lnas_obj.geometry, &joined_vertices, &joined_triangles);
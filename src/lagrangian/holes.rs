@@ -0,0 +1,406 @@
+use crate::lagrangian::mesh::{
+    edge_key, get_edges_triangles, get_triangles_surfaces, triangle_directed_edges, Edge,
+};
+use crate::utils::{Vec3f, Vec3u};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Surface name used for patches when filled holes are not added to their adjacent surface
+pub const FILLED_HOLES_SURFACE: &str = "filled_holes";
+
+/// Hole closed with a triangulated patch
+#[derive(Clone, Debug, PartialEq)]
+pub struct FilledHole {
+    pub surface_name: String,
+    pub n_edges: usize,
+    pub perimeter: f32,
+    pub centroid: Vec3f,
+    pub n_triangles: usize,
+}
+
+impl fmt::Display for FilledHole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(
+            f,
+            "hole at {} with {} edges (perimeter {:.4e}) filled with {} triangles in surface '{}'",
+            self.centroid, self.n_edges, self.perimeter, self.n_triangles, self.surface_name
+        );
+    }
+}
+
+/// Holes filled, listed by surface
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct FillHolesReport {
+    pub holes: Vec<FilledHole>,
+    /// Holes not filled because their patch would cross a feature edge
+    pub n_across_features: usize,
+}
+
+impl FillHolesReport {
+    /// Number of holes and patch triangles of each surface, sorted by surface name
+    pub fn surfaces_holes(&self) -> Vec<(String, usize, usize)> {
+        let mut surfaces_holes: HashMap<&String, (usize, usize)> = HashMap::new();
+        for hole in self.holes.iter() {
+            let entry = surfaces_holes.entry(&hole.surface_name).or_default();
+            entry.0 += 1;
+            entry.1 += hole.n_triangles;
+        }
+        let mut surfaces_holes: Vec<(String, usize, usize)> = surfaces_holes
+            .into_iter()
+            .map(|(name, (n_holes, n_triangles))| (name.to_owned(), n_holes, n_triangles))
+            .collect();
+        surfaces_holes.sort();
+        return surfaces_holes;
+    }
+}
+
+impl fmt::Display for FillHolesReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Filled {} holes", self.holes.len())?;
+        if self.n_across_features > 0 {
+            write!(
+                f,
+                " (skipped {} holes across feature edges)",
+                self.n_across_features
+            )?;
+        }
+        for (surface_name, n_holes, n_triangles) in self.surfaces_holes().iter() {
+            write!(
+                f,
+                "\n  '{}': {} holes filled with {} triangles",
+                surface_name, n_holes, n_triangles
+            )?;
+        }
+        for hole in self.holes.iter() {
+            write!(f, "\n  {}", hole)?;
+        }
+        return Ok(());
+    }
+}
+
+/// Loops of boundary edges, with vertices following the direction of their triangles edges.
+///
+/// Boundary edges that don't close a loop (e.g. inconsistent orientation) are ignored.
+pub fn get_boundary_loops(triangles: &Vec<Vec3u>) -> Vec<Vec<u32>> {
+    let edges_triangles = get_edges_triangles(triangles);
+    let mut next_vertices: HashMap<u32, Vec<u32>> = HashMap::new();
+    for t in triangles.iter() {
+        for (v0, v1) in triangle_directed_edges(t) {
+            if edges_triangles.get(&edge_key(v0, v1)).unwrap().len() == 1 {
+                next_vertices.entry(v0).or_default().push(v1);
+            }
+        }
+    }
+
+    // Do this to be deterministic
+    let mut start_vertices: Vec<u32> = next_vertices.keys().cloned().collect();
+    start_vertices.sort();
+    for v in next_vertices.values_mut() {
+        v.sort();
+    }
+
+    let mut loops: Vec<Vec<u32>> = Vec::new();
+    for start in start_vertices.into_iter() {
+        // Each unused boundary edge leaving vertex may start a new loop
+        while let Some(first) = next_vertices.get_mut(&start).unwrap().pop() {
+            let mut boundary_loop: Vec<u32> = vec![start];
+            let mut curr = first;
+            let mut closed = false;
+            loop {
+                if curr == start {
+                    closed = true;
+                    break;
+                }
+                boundary_loop.push(curr);
+                match next_vertices.get_mut(&curr).and_then(|v| v.pop()) {
+                    Some(next) => curr = next,
+                    None => break,
+                }
+            }
+            if closed {
+                loops.push(boundary_loop);
+            }
+        }
+    }
+    return loops;
+}
+
+/// Surface with most triangles adjacent to the boundary loop
+fn get_loop_surface(
+    boundary_loop: &Vec<u32>,
+    edges_triangles: &HashMap<Edge, Vec<usize>>,
+    triangles_surfaces: &Vec<Option<String>>,
+) -> Option<String> {
+    let n = boundary_loop.len();
+    let mut count: HashMap<&String, usize> = HashMap::new();
+    for i in 0..n {
+        let edge = edge_key(boundary_loop[i], boundary_loop[(i + 1) % n]);
+        for t_idx in edges_triangles.get(&edge).unwrap().iter() {
+            if let Some(surface_name) = &triangles_surfaces[*t_idx] {
+                *count.entry(surface_name).or_default() += 1;
+            }
+        }
+    }
+    // Ties are decided by surface name, to be deterministic
+    return count
+        .into_iter()
+        .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(a.0)))
+        .map(|(s, _)| s.to_owned());
+}
+
+/// If fan around centroid has neighbour triangles with normals angle above the feature angle.
+///
+/// Such a patch would bend across a feature edge, merging the features around the hole.
+fn fan_crosses_features(points: &Vec<Vec3f>, centroid: Vec3f, min_cos: f32) -> bool {
+    let n = points.len();
+    let normals: Vec<Vec3f> = (0..n)
+        .map(|i| {
+            let mut normal = (points[i] - centroid).cross(points[(i + 1) % n] - centroid);
+            normal.normalize();
+            normal
+        })
+        .collect();
+    // Degenerated fan triangles (NaN normals) don't define a direction to compare
+    return (0..n).any(|i| normals[i].dot(normals[(i + 1) % n]) < min_cos);
+}
+
+/// Triangulate boundary loop, returning the patch triangles.
+///
+/// Loops with more than three edges are triangulated as a fan around a new vertex in its centroid.
+fn triangulate_loop(
+    boundary_loop: &Vec<u32>,
+    vertices: &mut Vec<Vec3f>,
+    centroid: Vec3f,
+) -> Vec<Vec3u> {
+    let n = boundary_loop.len();
+    // Patch must traverse loop edges in opposite direction to be consistent with neighbours
+    if n == 3 {
+        return vec![Vec3u {
+            x: boundary_loop[0],
+            y: boundary_loop[2],
+            z: boundary_loop[1],
+        }];
+    }
+    let c_idx = vertices.len() as u32;
+    vertices.push(centroid);
+    return (0..n)
+        .map(|i| Vec3u {
+            x: boundary_loop[(i + 1) % n],
+            y: boundary_loop[i],
+            z: c_idx,
+        })
+        .collect();
+}
+
+/// Fill boundary loops with perimeter up to `max_perimeter`.
+///
+/// Every loop is considered a hole, including outer boundaries of open surfaces (such as the
+/// ends of an open tube), so `max_perimeter` must be below them to keep these surfaces open.
+/// Loops whose fan would bend more than `feature_angle` (in degrees) are not filled, as the
+/// patch would cross the feature edges around them.
+/// Patches are added to the surface adjacent to the hole or, if `holes_surface` is given,
+/// to the surface with that name.
+pub fn fill_holes(
+    vertices: &mut Vec<Vec3f>,
+    triangles: &mut Vec<Vec3u>,
+    surfaces: &mut HashMap<String, Vec<u32>>,
+    max_perimeter: f32,
+    holes_surface: Option<&str>,
+    feature_angle: f32,
+) -> FillHolesReport {
+    let min_cos = feature_angle.to_radians().cos();
+    let boundary_loops = get_boundary_loops(triangles);
+    let edges_triangles = get_edges_triangles(triangles);
+    let triangles_surfaces = get_triangles_surfaces(triangles.len(), surfaces);
+
+    let mut filled_holes: Vec<FilledHole> = Vec::new();
+    let mut n_across_features = 0;
+    let mut patches_triangles: Vec<(String, Vec3u)> = Vec::new();
+    for boundary_loop in boundary_loops.iter() {
+        let n = boundary_loop.len();
+        if n < 3 {
+            continue;
+        }
+        let points: Vec<Vec3f> = boundary_loop
+            .iter()
+            .map(|v| vertices[*v as usize])
+            .collect();
+        let perimeter: f32 = (0..n)
+            .map(|i| (points[(i + 1) % n] - points[i]).norm())
+            .sum();
+        if perimeter > max_perimeter {
+            continue;
+        }
+        let mut centroid = points.iter().fold(
+            Vec3f {
+                x: 0f32,
+                y: 0f32,
+                z: 0f32,
+            },
+            |acc, p| acc + *p,
+        );
+        centroid.divide(n as f32);
+        if n > 3 && fan_crosses_features(&points, centroid, min_cos) {
+            n_across_features += 1;
+            continue;
+        }
+
+        let surface_name = match holes_surface {
+            Some(s) => s.to_string(),
+            None => get_loop_surface(boundary_loop, &edges_triangles, &triangles_surfaces)
+                .unwrap_or(FILLED_HOLES_SURFACE.to_string()),
+        };
+        let patch = triangulate_loop(boundary_loop, vertices, centroid);
+        filled_holes.push(FilledHole {
+            surface_name: surface_name.clone(),
+            n_edges: n,
+            perimeter,
+            centroid,
+            n_triangles: patch.len(),
+        });
+        for t in patch.into_iter() {
+            patches_triangles.push((surface_name.clone(), t));
+        }
+    }
+
+    for (surface_name, t) in patches_triangles.into_iter() {
+        surfaces
+            .entry(surface_name)
+            .or_default()
+            .push(triangles.len() as u32);
+        triangles.push(t);
+    }
+    return FillHolesReport {
+        holes: filled_holes,
+        n_across_features,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lagrangian::mesh::triangle_normal;
+    use crate::lagrangian::quality::analyze_mesh;
+    use crate::lagrangian::test_utils::get_joined_stls;
+
+    /// Remove triangles from cube, updating its surface
+    fn remove_triangles(
+        triangles: &mut Vec<Vec3u>,
+        surfaces: &mut HashMap<String, Vec<u32>>,
+        t_idxs: &[usize],
+    ) {
+        let mut keep_triangles: Vec<Vec3u> = Vec::new();
+        for (t_idx, t) in triangles.iter().enumerate() {
+            if !t_idxs.contains(&t_idx) {
+                keep_triangles.push(*t);
+            }
+        }
+        *triangles = keep_triangles;
+        surfaces.insert("cube".to_string(), (0..triangles.len() as u32).collect());
+    }
+
+    #[test]
+    fn check_fill_triangle_hole() {
+        let (mut vertices, mut triangles, mut surfaces) = get_joined_stls(&["cube"]);
+        remove_triangles(&mut triangles, &mut surfaces, &[5]);
+        assert_eq!(get_boundary_loops(&triangles).len(), 1);
+
+        let filled = fill_holes(
+            &mut vertices,
+            &mut triangles,
+            &mut surfaces,
+            100f32,
+            None,
+            30f32,
+        );
+        assert_eq!(filled.holes.len(), 1);
+        assert_eq!(filled.holes[0].n_edges, 3);
+        assert_eq!(filled.holes[0].n_triangles, 1);
+        assert_eq!(filled.holes[0].surface_name, "cube");
+        assert_eq!(filled.surfaces_holes(), [("cube".to_string(), 1, 1)]);
+        assert_eq!(triangles.len(), 12);
+        assert_eq!(surfaces.get("cube").unwrap().len(), 12);
+
+        let quality = analyze_mesh(&vertices, &triangles);
+        assert!(quality.is_watertight());
+        assert!(quality.is_consistently_oriented());
+    }
+
+    #[test]
+    fn check_fill_face_hole() {
+        let (mut vertices, mut triangles, mut surfaces) = get_joined_stls(&["cube"]);
+        // Find the triangle sharing the diagonal of the first triangle's face
+        let edges_triangles = get_edges_triangles(&triangles);
+        let normal = triangle_normal(&vertices, &triangles[0]);
+        let pair_idx = triangle_directed_edges(&triangles[0])
+            .iter()
+            .flat_map(|(v0, v1)| edges_triangles.get(&edge_key(*v0, *v1)).unwrap().clone())
+            .find(|t_idx| {
+                *t_idx != 0 && triangle_normal(&vertices, &triangles[*t_idx]).dot(normal) > 0.99
+            })
+            .unwrap();
+        remove_triangles(&mut triangles, &mut surfaces, &[0, pair_idx]);
+
+        let filled = fill_holes(
+            &mut vertices,
+            &mut triangles,
+            &mut surfaces,
+            100f32,
+            Some(FILLED_HOLES_SURFACE),
+            30f32,
+        );
+        assert_eq!(filled.holes.len(), 1);
+        assert_eq!(filled.holes[0].n_edges, 4);
+        assert_eq!(vertices.len(), 9);
+        assert_eq!(surfaces.get(FILLED_HOLES_SURFACE).unwrap().len(), 4);
+
+        let quality = analyze_mesh(&vertices, &triangles);
+        assert!(quality.is_watertight());
+        assert!(quality.is_consistently_oriented());
+    }
+
+    #[test]
+    fn check_fill_ignores_large_holes() {
+        let (mut vertices, mut triangles, mut surfaces) = get_joined_stls(&["cube"]);
+        remove_triangles(&mut triangles, &mut surfaces, &[0]);
+        let filled = fill_holes(
+            &mut vertices,
+            &mut triangles,
+            &mut surfaces,
+            1e-3,
+            None,
+            30f32,
+        );
+        assert_eq!(filled.holes.len(), 0);
+        assert_eq!(triangles.len(), 11);
+    }
+
+    #[test]
+    fn check_fill_skips_holes_across_features() {
+        let (mut vertices, mut triangles, mut surfaces) = get_joined_stls(&["cube"]);
+        // Remove two triangles of different faces sharing a cube edge
+        let edges_triangles = get_edges_triangles(&triangles);
+        let normal = triangle_normal(&vertices, &triangles[0]);
+        let pair_idx = triangle_directed_edges(&triangles[0])
+            .iter()
+            .flat_map(|(v0, v1)| edges_triangles.get(&edge_key(*v0, *v1)).unwrap().clone())
+            .find(|t_idx| {
+                *t_idx != 0 && triangle_normal(&vertices, &triangles[*t_idx]).dot(normal) < 0.01
+            })
+            .unwrap();
+        remove_triangles(&mut triangles, &mut surfaces, &[0, pair_idx]);
+
+        let filled = fill_holes(
+            &mut vertices,
+            &mut triangles,
+            &mut surfaces,
+            100f32,
+            None,
+            30f32,
+        );
+        assert_eq!(filled.holes.len(), 0);
+        assert_eq!(filled.n_across_features, 1);
+        assert_eq!(triangles.len(), 10);
+        assert_eq!(vertices.len(), 8);
+    }
+}
@@ -0,0 +1,251 @@
+use crate::lagrangian::bvh::Bvh;
+use crate::lagrangian::mesh::{get_triangles_surfaces, triangle_normal, triangle_points};
+use crate::stl::triangle::TriangleSTL;
+use crate::utils::{Vec3f, Vec3u};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+
+/// Pair of triangles that cross each other
+#[derive(Clone, Debug, PartialEq)]
+pub struct TriangleIntersection {
+    pub triangles: (usize, usize),
+    pub surfaces: (String, String),
+    /// Mean of the points where edges cross the other triangle
+    pub location: Vec3f,
+}
+
+impl fmt::Display for TriangleIntersection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(
+            f,
+            "triangles {} ('{}') and {} ('{}') intersect at {}",
+            self.triangles.0, self.surfaces.0, self.triangles.1, self.surfaces.1, self.location
+        );
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct IntersectionReport {
+    pub intersections: Vec<TriangleIntersection>,
+}
+
+impl IntersectionReport {
+    pub fn is_valid(&self) -> bool {
+        return self.intersections.is_empty();
+    }
+
+    /// Number of intersecting pairs for each pair of surfaces
+    pub fn surfaces_pairs(&self) -> BTreeMap<(String, String), usize> {
+        let mut pairs: BTreeMap<(String, String), usize> = BTreeMap::new();
+        for intersection in self.intersections.iter() {
+            let (s0, s1) = &intersection.surfaces;
+            let key = match s0 <= s1 {
+                true => (s0.to_owned(), s1.to_owned()),
+                false => (s1.to_owned(), s0.to_owned()),
+            };
+            *pairs.entry(key).or_default() += 1;
+        }
+        return pairs;
+    }
+
+    /// Triangles that intersect any other, sorted
+    pub fn intersecting_triangles(&self) -> Vec<usize> {
+        let triangles: BTreeSet<usize> = self
+            .intersections
+            .iter()
+            .flat_map(|i| [i.triangles.0, i.triangles.1])
+            .collect();
+        return triangles.into_iter().collect();
+    }
+}
+
+impl fmt::Display for IntersectionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Found {} intersecting triangles pairs",
+            self.intersections.len()
+        )?;
+        for ((s0, s1), count) in self.surfaces_pairs().iter() {
+            write!(f, "\n  '{}' and '{}': {} pairs", s0, s1, count)?;
+        }
+        let max_listed = 10;
+        for intersection in self.intersections.iter().take(max_listed) {
+            write!(f, "\n  {}", intersection)?;
+        }
+        if self.intersections.len() > max_listed {
+            write!(
+                f,
+                "\n  ... and {} more",
+                self.intersections.len() - max_listed
+            )?;
+        }
+        return Ok(());
+    }
+}
+
+/// Point where segment `p0`-`p1` crosses triangle, if any (Möller–Trumbore).
+///
+/// Segments parallel to triangle plane are considered as not crossing it.
+fn segment_triangle_intersection(p0: Vec3f, p1: Vec3f, triangle: &[Vec3f; 3]) -> Option<Vec3f> {
+    let eps = 1e-6f32;
+    let direction = p1 - p0;
+    let edge1 = triangle[1] - triangle[0];
+    let edge2 = triangle[2] - triangle[0];
+    let h = direction.cross(edge2);
+    let det = edge1.dot(h);
+    if det.abs() <= eps * direction.norm() * edge1.norm() * edge2.norm() {
+        return None;
+    }
+    let s = p0 - triangle[0];
+    let u = s.dot(h) / det;
+    if !(0f32..=1f32).contains(&u) {
+        return None;
+    }
+    let q = s.cross(edge1);
+    let v = direction.dot(q) / det;
+    if v < 0f32 || u + v > 1f32 {
+        return None;
+    }
+    let t = edge2.dot(q) / det;
+    if !(0f32..=1f32).contains(&t) {
+        return None;
+    }
+    let mut offset = direction;
+    offset.multiply(t);
+    return Some(p0 + offset);
+}
+
+/// Points where edges of each triangle cross the other triangle
+pub fn triangles_intersection_points(t0: &[Vec3f; 3], t1: &[Vec3f; 3]) -> Vec<Vec3f> {
+    let mut points: Vec<Vec3f> = Vec::new();
+    for (edges_of, other) in [(t0, t1), (t1, t0)] {
+        for i in 0..3 {
+            if let Some(p) =
+                segment_triangle_intersection(edges_of[i], edges_of[(i + 1) % 3], other)
+            {
+                points.push(p);
+            }
+        }
+    }
+    return points;
+}
+
+fn share_vertex(t0: &Vec3u, t1: &Vec3u) -> bool {
+    return [t0.x, t0.y, t0.z]
+        .iter()
+        .any(|v| *v == t1.x || *v == t1.y || *v == t1.z);
+}
+
+/// Find pairs of triangles that cross each other.
+///
+/// Pairs sharing a vertex (neighbours in mesh) and coplanar overlaps are not considered.
+pub fn find_intersections(
+    vertices: &Vec<Vec3f>,
+    triangles: &Vec<Vec3u>,
+    surfaces: &HashMap<String, Vec<u32>>,
+) -> IntersectionReport {
+    let bvh = Bvh::from_triangles(vertices, triangles);
+    let triangles_surfaces = get_triangles_surfaces(triangles.len(), surfaces);
+    let surface_name = |t_idx: usize| triangles_surfaces[t_idx].clone().unwrap_or_default();
+
+    let mut report = IntersectionReport::default();
+    for (t0_idx, t0) in triangles.iter().enumerate() {
+        let mut candidates = bvh.query(bvh.item_aabb(t0_idx));
+        candidates.sort();
+        let points0 = triangle_points(vertices, t0);
+        for t1_idx in candidates.into_iter() {
+            // Each pair is checked once
+            if t1_idx <= t0_idx || share_vertex(t0, &triangles[t1_idx]) {
+                continue;
+            }
+            let points1 = triangle_points(vertices, &triangles[t1_idx]);
+            let points = triangles_intersection_points(&points0, &points1);
+            if points.is_empty() {
+                continue;
+            }
+            let mut location = points.iter().fold(
+                Vec3f {
+                    x: 0f32,
+                    y: 0f32,
+                    z: 0f32,
+                },
+                |acc, p| acc + *p,
+            );
+            location.divide(points.len() as f32);
+            report.intersections.push(TriangleIntersection {
+                triangles: (t0_idx, t1_idx),
+                surfaces: (surface_name(t0_idx), surface_name(t1_idx)),
+                location,
+            });
+        }
+    }
+    return report;
+}
+
+/// Intersecting triangles as STL triangles, to be exported for debugging
+pub fn get_intersecting_stl_triangles(
+    vertices: &Vec<Vec3f>,
+    triangles: &Vec<Vec3u>,
+    report: &IntersectionReport,
+) -> Vec<TriangleSTL> {
+    return report
+        .intersecting_triangles()
+        .into_iter()
+        .map(|t_idx| {
+            let t = &triangles[t_idx];
+            let [p0, p1, p2] = triangle_points(vertices, t);
+            TriangleSTL::new(p0, p1, p2, triangle_normal(vertices, t))
+        })
+        .collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lagrangian::test_utils::get_joined_stls;
+
+    #[test]
+    fn check_no_intersections_sphere() {
+        let (vertices, triangles, surfaces) = get_joined_stls(&["sphere"]);
+        let report = find_intersections(&vertices, &triangles, &surfaces);
+        assert!(report.is_valid());
+    }
+
+    #[test]
+    fn check_intersections_shifted_cube() {
+        let (mut vertices, mut triangles, mut surfaces) = get_joined_stls(&["cube"]);
+        // Add copy of cube shifted by half its side, crossing the original
+        let shift = Vec3f {
+            x: 10f32,
+            y: 10f32,
+            z: 10f32,
+        };
+        let n_vertices = vertices.len() as u32;
+        let n_triangles = triangles.len() as u32;
+        let shifted: Vec<Vec3f> = vertices.iter().map(|v| *v + shift).collect();
+        vertices.extend(shifted);
+        let copied: Vec<Vec3u> = triangles
+            .iter()
+            .map(|t| Vec3u {
+                x: t.x + n_vertices,
+                y: t.y + n_vertices,
+                z: t.z + n_vertices,
+            })
+            .collect();
+        triangles.extend(copied);
+        surfaces.insert(
+            "shifted".to_string(),
+            (n_triangles..2 * n_triangles).collect(),
+        );
+
+        let report = find_intersections(&vertices, &triangles, &surfaces);
+        assert!(!report.is_valid());
+        let pairs = report.surfaces_pairs();
+        assert_eq!(pairs.len(), 1);
+        assert!(pairs.contains_key(&("cube".to_string(), "shifted".to_string())));
+
+        let stl_triangles = get_intersecting_stl_triangles(&vertices, &triangles, &report);
+        assert_eq!(stl_triangles.len(), report.intersecting_triangles().len());
+    }
+}
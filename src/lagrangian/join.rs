@@ -1,3 +1,4 @@
+use crate::lagrangian::features::FeatureEdges;
 use crate::lagrangian::triangle::LagrangianTriangle;
 use crate::lagrangian::vertice::LagrangianVertice;
 use crate::utils::{Vec3f, Vec3u};
@@ -47,13 +48,52 @@ pub fn join_information(
     return (vec_vertices, vec_triangles);
 }
 
+/// Renumber vertices in order of first appearance in triangles.
+///
+/// Neighbour triangles then reference close indices, which makes encoded triangles indices
+/// small. Unreferenced vertices are kept at the end, and features are renumbered as well
+pub fn reorder_vertices(
+    vertices: &Vec<Vec3f>,
+    triangles: &Vec<Vec3u>,
+    features: Option<&mut FeatureEdges>,
+) -> (Vec<Vec3f>, Vec<Vec3u>) {
+    let mut new_idxs: Vec<Option<u32>> = vec![None; vertices.len()];
+    let mut new_vertices: Vec<Vec3f> = Vec::with_capacity(vertices.len());
+    let mut new_triangles: Vec<Vec3u> = Vec::with_capacity(triangles.len());
+
+    let mut get_new_idx = |idx: u32| -> u32 {
+        if let Some(new_idx) = new_idxs[idx as usize] {
+            return new_idx;
+        }
+        let new_idx = new_vertices.len() as u32;
+        new_idxs[idx as usize] = Some(new_idx);
+        new_vertices.push(vertices[idx as usize]);
+        return new_idx;
+    };
+    for t in triangles.iter() {
+        let x = get_new_idx(t.x);
+        let y = get_new_idx(t.y);
+        let z = get_new_idx(t.z);
+        new_triangles.push(Vec3u { x, y, z });
+    }
+    for (idx, vertice) in vertices.iter().enumerate() {
+        if new_idxs[idx].is_none() {
+            new_idxs[idx] = Some(new_vertices.len() as u32);
+            new_vertices.push(*vertice);
+        }
+    }
+    if let Some(features) = features {
+        let new_idxs: Vec<u32> = new_idxs.into_iter().map(|idx| idx.unwrap()).collect();
+        *features = features.renumber_vertices(&new_idxs);
+    }
+    return (new_vertices, new_triangles);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::lagrangian::triangle::generate_lagrangian_triangles;
-    use crate::lagrangian::vertice::generate_lagrangian_vertices;
-    use crate::stl::reader::read_stl;
-    use std::path;
+    use crate::lagrangian::features::detect_feature_edges;
+    use crate::lagrangian::test_utils::get_lagrangian_stls;
 
     fn check_vertices_compatibility(
         lagrangian_vertices: &HashMap<LagrangianVertice, usize>,
@@ -104,10 +144,7 @@ mod tests {
 
     #[test]
     fn check_join_info_stl_cube() {
-        let filename = path::Path::new(String::from("examples/stl/cube.stl").as_str()).to_owned();
-        let triangles = read_stl(&filename);
-        let lagr_vertices = generate_lagrangian_vertices(&triangles);
-        let lagr_triangles = generate_lagrangian_triangles(&lagr_vertices, &triangles);
+        let (lagr_vertices, lagr_triangles, _) = get_lagrangian_stls(&["cube"]);
         let (joined_vert, joined_tri) = join_information(&lagr_vertices, &lagr_triangles);
 
         check_vertices_compatibility(&lagr_vertices, &joined_vert);
@@ -116,14 +153,32 @@ mod tests {
 
     #[test]
     fn check_join_infocar_stl_terrain() {
-        let filename =
-            path::Path::new(String::from("examples/stl/terrain.stl").as_str()).to_owned();
-        let triangles = read_stl(&filename);
-        let lagr_vertices = generate_lagrangian_vertices(&triangles);
-        let lagr_triangles = generate_lagrangian_triangles(&lagr_vertices, &triangles);
+        let (lagr_vertices, lagr_triangles, _) = get_lagrangian_stls(&["terrain"]);
         let (joined_vert, joined_tri) = join_information(&lagr_vertices, &lagr_triangles);
 
         check_vertices_compatibility(&lagr_vertices, &joined_vert);
         check_triangles_compatibility(&lagr_triangles, &joined_vert, &joined_tri);
     }
+
+    #[test]
+    fn check_reorder_vertices_terrain() {
+        let (lagr_vertices, lagr_triangles, _) = get_lagrangian_stls(&["terrain"]);
+        let (joined_vert, joined_tri) = join_information(&lagr_vertices, &lagr_triangles);
+        let mut features = detect_feature_edges(&joined_vert, &joined_tri, 30f32);
+        let (reordered_vert, reordered_tri) =
+            reorder_vertices(&joined_vert, &joined_tri, Some(&mut features));
+
+        assert_eq!(reordered_vert.len(), joined_vert.len());
+        check_triangles_compatibility(&lagr_triangles, &reordered_vert, &reordered_tri);
+        // New vertices are always next to the largest index seen so far
+        let mut max_idx = 0;
+        for t in reordered_tri.iter() {
+            for idx in [t.x, t.y, t.z] {
+                assert!(idx <= max_idx + 1);
+                max_idx = max_idx.max(idx);
+            }
+        }
+        // Same features as detected on reordered mesh
+        assert!(features == detect_feature_edges(&reordered_vert, &reordered_tri, 30f32));
+    }
 }
@@ -0,0 +1,196 @@
+use crate::lagrangian::mesh::{get_edges_triangles, triangle_area, triangle_points};
+use crate::utils::{Vec3f, Vec3u};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// Upper limits of histogram bins, relative to lattice (dx for lengths, dx² for areas)
+const HISTOGRAM_BINS: [f32; 8] = [0.125, 0.25, 0.5, 1.0, 2.0, 4.0, 8.0, f32::INFINITY];
+
+/// Count of values by bins, relative to lattice spacing
+#[derive(Clone, Debug, PartialEq)]
+pub struct Histogram {
+    pub bins: Vec<f32>,
+    pub counts: Vec<usize>,
+}
+
+impl Histogram {
+    fn new(values: impl Iterator<Item = f32>) -> Histogram {
+        let bins = HISTOGRAM_BINS.to_vec();
+        let mut counts: Vec<usize> = vec![0; bins.len()];
+        for v in values {
+            let bin_idx = bins.iter().position(|b| v < *b).unwrap_or(bins.len() - 1);
+            counts[bin_idx] += 1;
+        }
+        return Histogram { bins, counts };
+    }
+}
+
+impl fmt::Display for Histogram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total: usize = self.counts.iter().sum();
+        let mut lower = 0f32;
+        for (upper, count) in self.bins.iter().zip(self.counts.iter()) {
+            let percent = 100f32 * (*count as f32) / (total.max(1) as f32);
+            writeln!(
+                f,
+                "    [{:>5}, {:>5}): {:>8} ({:5.1}%)",
+                lower, upper, count, percent
+            )?;
+            lower = *upper;
+        }
+        return Ok(());
+    }
+}
+
+/// Lagrangian nodes density of a surface
+#[derive(Clone, Debug, PartialEq)]
+pub struct SurfaceDensity {
+    pub surface_name: String,
+    pub n_nodes: usize,
+    pub area: f32,
+    /// Number of nodes for each dx² of surface area
+    pub nodes_per_cell: f32,
+}
+
+/// Compatibility of lagrangian mesh with a lattice spacing
+#[derive(Clone, Debug, PartialEq)]
+pub struct LatticeReport {
+    pub dx: f32,
+    pub max_dx_ratio: f32,
+    /// Edges lengths divided by dx
+    pub edges_histogram: Histogram,
+    /// Triangles areas divided by dx²
+    pub areas_histogram: Histogram,
+    /// Triangles with an edge longer than `max_dx_ratio * dx`
+    pub large_triangles: Vec<usize>,
+    pub surfaces_density: Vec<SurfaceDensity>,
+}
+
+impl LatticeReport {
+    pub fn is_valid(&self) -> bool {
+        return self.large_triangles.is_empty();
+    }
+}
+
+impl fmt::Display for LatticeReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Lattice check for dx={}", self.dx)?;
+        writeln!(f, "  Edges lengths (relative to dx):")?;
+        write!(f, "{}", self.edges_histogram)?;
+        writeln!(f, "  Triangles areas (relative to dx²):")?;
+        write!(f, "{}", self.areas_histogram)?;
+        writeln!(f, "  Nodes density (nodes per dx²):")?;
+        for density in self.surfaces_density.iter() {
+            writeln!(
+                f,
+                "    {}: {:.4} ({} nodes in area {:.4e})",
+                density.surface_name, density.nodes_per_cell, density.n_nodes, density.area
+            )?;
+        }
+        return write!(
+            f,
+            "  Triangles with edges longer than {} dx: {}",
+            self.max_dx_ratio,
+            self.large_triangles.len()
+        );
+    }
+}
+
+fn get_surface_density(
+    surface_name: &str,
+    triangles_idxs: &Vec<u32>,
+    vertices: &Vec<Vec3f>,
+    triangles: &Vec<Vec3u>,
+    dx: f32,
+) -> SurfaceDensity {
+    let mut nodes: HashSet<u32> = HashSet::new();
+    let mut area = 0f32;
+    for t_idx in triangles_idxs.iter() {
+        let t = &triangles[*t_idx as usize];
+        nodes.extend([t.x, t.y, t.z]);
+        area += triangle_area(vertices, t);
+    }
+    let n_cells = area / (dx * dx);
+    return SurfaceDensity {
+        surface_name: surface_name.to_string(),
+        n_nodes: nodes.len(),
+        area,
+        nodes_per_cell: nodes.len() as f32 / n_cells,
+    };
+}
+
+/// Check edges and triangles sizes relative to lattice spacing `dx`.
+///
+/// Triangles with any edge longer than `max_dx_ratio * dx` are flagged.
+pub fn check_lattice(
+    vertices: &Vec<Vec3f>,
+    triangles: &Vec<Vec3u>,
+    surfaces: &HashMap<String, Vec<u32>>,
+    dx: f32,
+    max_dx_ratio: f32,
+) -> LatticeReport {
+    let edges_lengths = get_edges_triangles(triangles)
+        .into_keys()
+        .map(|(v0, v1)| (vertices[v0 as usize] - vertices[v1 as usize]).norm() / dx);
+    let edges_histogram = Histogram::new(edges_lengths);
+    let areas_histogram = Histogram::new(
+        triangles
+            .iter()
+            .map(|t| triangle_area(vertices, t) / (dx * dx)),
+    );
+
+    let large_triangles: Vec<usize> = triangles
+        .iter()
+        .enumerate()
+        .filter(|(_, t)| {
+            let [p0, p1, p2] = triangle_points(vertices, t);
+            let max_edge = [(p1 - p0).norm(), (p2 - p1).norm(), (p0 - p2).norm()]
+                .into_iter()
+                .fold(0f32, f32::max);
+            max_edge > max_dx_ratio * dx
+        })
+        .map(|(t_idx, _)| t_idx)
+        .collect();
+
+    let mut surface_names: Vec<&String> = surfaces.keys().collect();
+    surface_names.sort();
+    let surfaces_density: Vec<SurfaceDensity> = surface_names
+        .into_iter()
+        .map(|s| get_surface_density(s, surfaces.get(s).unwrap(), vertices, triangles, dx))
+        .collect();
+
+    return LatticeReport {
+        dx,
+        max_dx_ratio,
+        edges_histogram,
+        areas_histogram,
+        large_triangles,
+        surfaces_density,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lagrangian::test_utils::get_joined_stls;
+
+    #[test]
+    fn check_lattice_cube() {
+        let (vertices, triangles, surfaces) = get_joined_stls(&["cube"]);
+        let xs: Vec<f32> = vertices.iter().map(|v| v.x).collect();
+        let side = xs.iter().cloned().fold(f32::MIN, f32::max)
+            - xs.iter().cloned().fold(f32::MAX, f32::min);
+
+        // Diagonals are longer than side
+        let report = check_lattice(&vertices, &triangles, &surfaces, side, 1.2);
+        assert!(!report.is_valid());
+        assert_eq!(report.large_triangles.len(), 12);
+        assert_eq!(report.edges_histogram.counts.iter().sum::<usize>(), 18);
+        assert_eq!(report.areas_histogram.counts.iter().sum::<usize>(), 12);
+
+        let report = check_lattice(&vertices, &triangles, &surfaces, side, 1.5);
+        assert!(report.is_valid());
+        assert_eq!(report.surfaces_density.len(), 1);
+        assert_eq!(report.surfaces_density[0].n_nodes, 8);
+    }
+}
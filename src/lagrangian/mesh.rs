@@ -0,0 +1,142 @@
+use crate::utils::{Vec3f, Vec3u};
+use std::collections::HashMap;
+
+/// Undirected edge, represented by its vertices indexes sorted
+pub type Edge = (u32, u32);
+
+pub fn edge_key(v0: u32, v1: u32) -> Edge {
+    if v0 < v1 {
+        return (v0, v1);
+    }
+    return (v1, v0);
+}
+
+/// Directed edges of triangle, following its points order (p0->p1, p1->p2, p2->p0)
+pub fn triangle_directed_edges(triangle: &Vec3u) -> [(u32, u32); 3] {
+    return [
+        (triangle.x, triangle.y),
+        (triangle.y, triangle.z),
+        (triangle.z, triangle.x),
+    ];
+}
+
+pub fn triangle_points(vertices: &Vec<Vec3f>, triangle: &Vec3u) -> [Vec3f; 3] {
+    return [
+        vertices[triangle.x as usize],
+        vertices[triangle.y as usize],
+        vertices[triangle.z as usize],
+    ];
+}
+
+/// Triangle normal scaled by two times its area, following right hand rule
+pub fn triangle_cross(vertices: &Vec<Vec3f>, triangle: &Vec3u) -> Vec3f {
+    let [p0, p1, p2] = triangle_points(vertices, triangle);
+    return (p1 - p0).cross(p2 - p0);
+}
+
+pub fn triangle_area(vertices: &Vec<Vec3f>, triangle: &Vec3u) -> f32 {
+    return triangle_cross(vertices, triangle).norm() / 2f32;
+}
+
+pub fn triangle_normal(vertices: &Vec<Vec3f>, triangle: &Vec3u) -> Vec3f {
+    let mut normal = triangle_cross(vertices, triangle);
+    normal.normalize();
+    return normal;
+}
+
+pub fn triangle_centroid(vertices: &Vec<Vec3f>, triangle: &Vec3u) -> Vec3f {
+    let [p0, p1, p2] = triangle_points(vertices, triangle);
+    let mut centroid = p0 + p1 + p2;
+    centroid.divide(3f32);
+    return centroid;
+}
+
+/// Map of each edge to the triangles that use it
+pub fn get_edges_triangles(triangles: &Vec<Vec3u>) -> HashMap<Edge, Vec<usize>> {
+    let mut edges_triangles: HashMap<Edge, Vec<usize>> = HashMap::new();
+    for (t_idx, t) in triangles.iter().enumerate() {
+        for (v0, v1) in triangle_directed_edges(t) {
+            edges_triangles
+                .entry(edge_key(v0, v1))
+                .or_default()
+                .push(t_idx);
+        }
+    }
+    return edges_triangles;
+}
+
+/// Groups of triangles connected by edges shared by exactly two triangles.
+///
+/// Each component is sorted and components are ordered by their first triangle.
+pub fn get_connected_components(
+    triangles: &Vec<Vec3u>,
+    edges_triangles: &HashMap<Edge, Vec<usize>>,
+) -> Vec<Vec<usize>> {
+    let mut component_of: Vec<Option<usize>> = vec![None; triangles.len()];
+    let mut components: Vec<Vec<usize>> = Vec::new();
+
+    for start in 0..triangles.len() {
+        if component_of[start].is_some() {
+            continue;
+        }
+        let comp_idx = components.len();
+        let mut component: Vec<usize> = Vec::new();
+        let mut stack: Vec<usize> = vec![start];
+        component_of[start] = Some(comp_idx);
+        while let Some(t_idx) = stack.pop() {
+            component.push(t_idx);
+            for (v0, v1) in triangle_directed_edges(&triangles[t_idx]) {
+                let neighbours = edges_triangles.get(&edge_key(v0, v1)).unwrap();
+                if neighbours.len() != 2 {
+                    continue;
+                }
+                for n_idx in neighbours.iter() {
+                    if component_of[*n_idx].is_none() {
+                        component_of[*n_idx] = Some(comp_idx);
+                        stack.push(*n_idx);
+                    }
+                }
+            }
+        }
+        component.sort();
+        components.push(component);
+    }
+    return components;
+}
+
+/// Surface of each triangle, the first by name if triangle is in more than one
+pub fn get_triangles_surfaces(
+    n_triangles: usize,
+    surfaces: &HashMap<String, Vec<u32>>,
+) -> Vec<Option<String>> {
+    let mut triangles_surfaces: Vec<Option<String>> = vec![None; n_triangles];
+    let mut surface_names: Vec<&String> = surfaces.keys().collect();
+    surface_names.sort();
+    for surface_name in surface_names.into_iter().rev() {
+        for t_idx in surfaces.get(surface_name).unwrap().iter() {
+            triangles_surfaces[*t_idx as usize] = Some(surface_name.to_owned());
+        }
+    }
+    return triangles_surfaces;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lagrangian::test_utils::get_joined_stl;
+
+    #[test]
+    fn check_edges_stl_cube() {
+        let (_, joined_tri) = get_joined_stl("cube");
+
+        let edges_triangles = get_edges_triangles(&joined_tri);
+        // Cube has 12 edges on its faces and 6 diagonals
+        assert_eq!(edges_triangles.len(), 18);
+        for (_, edge_triangles) in edges_triangles.iter() {
+            assert_eq!(edge_triangles.len(), 2);
+        }
+        let components = get_connected_components(&joined_tri, &edges_triangles);
+        assert_eq!(components.len(), 1);
+        assert_eq!(components[0], (0..12).collect::<Vec<usize>>());
+    }
+}
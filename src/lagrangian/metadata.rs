@@ -0,0 +1,147 @@
+use crate::utils::Vec3f;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::error::Error;
+use std::{fs, path};
+
+/// STL used to generate LNAS
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SourceMetadata {
+    /// Surface name given to STL
+    pub name: String,
+    pub filename: String,
+    /// SHA-256 of file content, as lowercase hex
+    pub sha256: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BoundingBoxMetadata {
+    pub min: [f64; 3],
+    pub max: [f64; 3],
+}
+
+/// Provenance of LNAS: when, how and from what it was generated
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MetadataLNAS {
+    /// Creation time, in RFC 3339
+    pub created_at: String,
+    pub tool: String,
+    pub tool_version: String,
+    /// Arguments the tool was called with, including its name
+    pub command_line: Vec<String>,
+    pub sources: Vec<SourceMetadata>,
+    /// Length unit of geometry (such as "m" or "mm")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub units: Option<String>,
+    /// Bounding box of vertices, in original coordinates (origin shift added back)
+    pub bounding_box: BoundingBoxMetadata,
+    pub n_vertices: usize,
+    pub n_triangles: usize,
+    pub n_surfaces: usize,
+}
+
+/// SHA-256 of file content, as lowercase hex
+pub fn sha256_file(filename: &path::Path) -> Result<String, Box<dyn Error>> {
+    let digest = Sha256::digest(fs::read(filename)?);
+    return Ok(digest.iter().map(|b| format!("{:02x}", b)).collect());
+}
+
+/// Sources sorted by name, with their content hash
+pub fn get_sources_metadata(
+    stls: &HashMap<String, path::PathBuf>,
+) -> Result<Vec<SourceMetadata>, Box<dyn Error>> {
+    let mut names: Vec<&String> = stls.keys().collect();
+    names.sort();
+    let mut sources: Vec<SourceMetadata> = Vec::with_capacity(names.len());
+    for name in names.into_iter() {
+        let filename = stls.get(name).unwrap();
+        sources.push(SourceMetadata {
+            name: name.to_owned(),
+            filename: filename.to_string_lossy().to_string(),
+            sha256: sha256_file(filename)?,
+        });
+    }
+    return Ok(sources);
+}
+
+pub fn get_bounding_box_metadata(
+    vertices: &Vec<Vec3f>,
+    origin_shift: Option<[f64; 3]>,
+) -> BoundingBoxMetadata {
+    let shift = origin_shift.unwrap_or([0f64; 3]);
+    let mut min = [f64::MAX; 3];
+    let mut max = [f64::MIN; 3];
+    for v in vertices.iter() {
+        for (i, value) in [v.x, v.y, v.z].into_iter().enumerate() {
+            min[i] = min[i].min(value as f64 + shift[i]);
+            max[i] = max[i].max(value as f64 + shift[i]);
+        }
+    }
+    if vertices.is_empty() {
+        return BoundingBoxMetadata {
+            min: shift,
+            max: shift,
+        };
+    }
+    return BoundingBoxMetadata { min, max };
+}
+
+impl MetadataLNAS {
+    /// Metadata of current run, with geometry fields to be filled from LNAS
+    pub fn new(
+        stls: &HashMap<String, path::PathBuf>,
+        command_line: Vec<String>,
+        units: Option<String>,
+    ) -> Result<MetadataLNAS, Box<dyn Error>> {
+        return Ok(MetadataLNAS {
+            created_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            tool: env!("CARGO_PKG_NAME").to_string(),
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            command_line,
+            sources: get_sources_metadata(stls)?,
+            units,
+            bounding_box: BoundingBoxMetadata {
+                min: [0f64; 3],
+                max: [0f64; 3],
+            },
+            n_vertices: 0,
+            n_triangles: 0,
+            n_surfaces: 0,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_sha256() {
+        let filename = path::Path::new("output/metadata/abc.txt");
+        crate::utils::create_folder_for_filename(filename).unwrap();
+        fs::write(filename, "abc").unwrap();
+        assert_eq!(
+            sha256_file(filename).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn check_sources_metadata() {
+        let mut stls: HashMap<String, path::PathBuf> = HashMap::new();
+        for name in ["plane", "cube"] {
+            stls.insert(
+                name.to_string(),
+                path::Path::new(&format!("examples/stl/{}.stl", name)).to_owned(),
+            );
+        }
+        let metadata =
+            MetadataLNAS::new(&stls, vec!["stl2lnas".to_string()], Some("m".to_string())).unwrap();
+        let names: Vec<&str> = metadata.sources.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["cube", "plane"]);
+        assert_eq!(metadata.sources[0].sha256.len(), 64);
+        assert_ne!(metadata.sources[0].sha256, metadata.sources[1].sha256);
+        assert!(chrono::DateTime::parse_from_rfc3339(&metadata.created_at).is_ok());
+    }
+}
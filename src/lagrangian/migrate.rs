@@ -0,0 +1,300 @@
+use crate::lagrangian::binary::{is_lnasb, read_lnasb_bytes};
+use crate::lagrangian::format::{encode_u32, SurfaceLNAS, LNAS};
+use serde_yaml::{Mapping, Value};
+use std::error::Error;
+use std::{fmt, fs, path};
+
+/// Surface with all triangles, for versions before surfaces existed (v0.4)
+pub const DEFAULT_SURFACE: &str = "default";
+
+/// Top level fields of current layout, others are dropped in migration
+const CURRENT_FIELDS: [&str; 7] = [
+    "version",
+    "compression",
+    "indices_encoding",
+    "metadata",
+    "geometry",
+    "surfaces",
+    "features",
+];
+
+/// Parse version as "v<major>.<minor>.<patch>"
+pub fn parse_version(version: &str) -> Result<(u32, u32, u32), Box<dyn Error>> {
+    let invalid = || {
+        format!(
+            "Invalid LNAS version '{}', expected 'v<major>.<minor>.<patch>'",
+            version
+        )
+    };
+    let numbers: Vec<&str> = version
+        .strip_prefix('v')
+        .ok_or_else(invalid)?
+        .split('.')
+        .collect();
+    if numbers.len() != 3 {
+        return Err(invalid().into());
+    }
+    let mut parsed = [0u32; 3];
+    for (i, n) in numbers.iter().enumerate() {
+        parsed[i] = n.parse().map_err(|_| invalid())?;
+    }
+    return Ok((parsed[0], parsed[1], parsed[2]));
+}
+
+pub fn current_version() -> String {
+    return format!("v{}", env!("CARGO_PKG_VERSION"));
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MigrationReport {
+    pub from_version: String,
+    pub to_version: String,
+    /// Surface created with all triangles, for files without surfaces
+    pub added_surface: Option<String>,
+    /// Top level fields not in current layout (such as v0.4 normalization)
+    pub dropped_fields: Vec<String>,
+}
+
+impl fmt::Display for MigrationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Migrated LNAS from {} to {}",
+            self.from_version, self.to_version
+        )?;
+        if let Some(surface_name) = &self.added_surface {
+            write!(f, "\n  Added surface '{}' with all triangles", surface_name)?;
+        }
+        for field in self.dropped_fields.iter() {
+            write!(f, "\n  Dropped field '{}'", field)?;
+        }
+        return Ok(());
+    }
+}
+
+/// Check that version may be migrated, returning if its layout has surfaces
+fn version_has_surfaces(version: &str) -> Result<bool, Box<dyn Error>> {
+    let (major, minor, _) = parse_version(version)?;
+    let (current_major, current_minor, _) = parse_version(&current_version())?;
+    return match (major, minor) {
+        (0, 2) | (0, 3) => Ok(false),
+        (0, 4) | (0, 5) => Ok(true),
+        (m, n) if (m, n) == (current_major, current_minor) => Ok(true),
+        _ => Err(format!(
+            "Unable to migrate LNAS version {}, supported versions are v0.2 to {}",
+            version,
+            current_version()
+        )
+        .into()),
+    };
+}
+
+/// Convert LNAS YAML content of any supported version (v0.2 to current) to current layout.
+///
+/// - v0.2 and v0.3: geometry only, all triangles are added to `DEFAULT_SURFACE`
+/// - v0.4 and v0.5: surfaces saved as name to triangles indexes
+/// - v0.6: current layout
+pub fn migrate_lnas(value: Value) -> Result<(LNAS, MigrationReport), Box<dyn Error>> {
+    let mut mapping: Mapping = match value {
+        Value::Mapping(mapping) => mapping,
+        _ => return Err("LNAS content is not a mapping".into()),
+    };
+    let from_version = mapping
+        .get("version")
+        .and_then(|v| v.as_str())
+        .ok_or("LNAS has no version")?
+        .to_string();
+    let has_surfaces = version_has_surfaces(&from_version)?;
+    let to_version = current_version();
+
+    let mut report = MigrationReport {
+        from_version,
+        to_version: to_version.clone(),
+        added_surface: None,
+        dropped_fields: Vec::new(),
+    };
+    let keys: Vec<Value> = mapping.keys().cloned().collect();
+    for key in keys.into_iter() {
+        let name = key.as_str().unwrap_or_default().to_string();
+        if !CURRENT_FIELDS.contains(&name.as_str()) {
+            mapping.remove(&key);
+            report.dropped_fields.push(name);
+        }
+    }
+    mapping.insert(Value::from("version"), Value::from(to_version));
+    let surfaces_missing = !has_surfaces || !mapping.contains_key("surfaces");
+    if surfaces_missing {
+        mapping.insert(Value::from("surfaces"), Value::Mapping(Mapping::new()));
+    }
+
+    let mut lnas_obj: LNAS = serde_yaml::from_value(Value::Mapping(mapping))
+        .map_err(|e| format!("Invalid LNAS {} layout. Error: {}", report.from_version, e))?;
+    if surfaces_missing {
+        let n_triangles = lnas_obj.n_triangles()?;
+        let triangles_idxs: Vec<u32> = (0..n_triangles as u32).collect();
+        lnas_obj.surfaces.insert(
+            DEFAULT_SURFACE.to_string(),
+            SurfaceLNAS {
+                triangles_idxs: encode_u32(&triangles_idxs),
+                attributes: None,
+            },
+        );
+        report.added_surface = Some(DEFAULT_SURFACE.to_string());
+    }
+    return Ok((lnas_obj, report));
+}
+
+/// Read LNAS file of any supported version, converting it to current layout
+pub fn migrate_lnas_file(filename: &path::Path) -> Result<(LNAS, MigrationReport), Box<dyn Error>> {
+    let content = fs::read(filename)?;
+    if is_lnasb(&content) {
+        // Binary layout only exists since v0.6, so there's nothing to convert
+        let mut lnas_obj = read_lnasb_bytes(&content)?;
+        version_has_surfaces(&lnas_obj.version)?;
+        let report = MigrationReport {
+            from_version: lnas_obj.version.clone(),
+            to_version: current_version(),
+            added_surface: None,
+            dropped_fields: Vec::new(),
+        };
+        lnas_obj.version = current_version();
+        return Ok((lnas_obj, report));
+    }
+    return migrate_lnas(serde_yaml::from_slice(&content)?);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lagrangian::format::{encode_bytes, encode_vec3f};
+    use crate::utils::{Vec3f, Vec3u};
+
+    /// Vertices and triangles of a unit square, as base64
+    fn get_square_arrays() -> (String, String) {
+        let vertices: Vec<Vec3f> = [(0f32, 0f32), (1f32, 0f32), (1f32, 1f32), (0f32, 1f32)]
+            .iter()
+            .map(|(x, y)| Vec3f {
+                x: *x,
+                y: *y,
+                z: 0f32,
+            })
+            .collect();
+        let triangles: Vec<u32> = vec![0, 1, 2, 0, 2, 3];
+        return (
+            encode_bytes(&encode_vec3f(&vertices).0),
+            encode_bytes(&encode_u32(&triangles).0),
+        );
+    }
+
+    fn check_square_geometry(lnas_obj: &LNAS) {
+        assert_eq!(lnas_obj.version, current_version());
+        assert_eq!(lnas_obj.get_vertices().unwrap().len(), 4);
+        let triangles = lnas_obj.get_triangles().unwrap();
+        assert!(triangles == vec![Vec3u { x: 0, y: 1, z: 2 }, Vec3u { x: 0, y: 2, z: 3 }]);
+    }
+
+    #[test]
+    fn check_migrate_v0_2() {
+        let (vertices, triangles) = get_square_arrays();
+        let value: Value = serde_yaml::from_str(&format!(
+            "version: v0.2.1\ngeometry:\n  vertices: {}\n  triangles: {}\n",
+            vertices, triangles
+        ))
+        .unwrap();
+
+        let (migrated, report) = migrate_lnas(value).unwrap();
+        check_square_geometry(&migrated);
+        assert_eq!(report.from_version, "v0.2.1");
+        assert_eq!(report.added_surface, Some(DEFAULT_SURFACE.to_string()));
+        assert!(report.dropped_fields.is_empty());
+        let surfaces = migrated.get_surfaces().unwrap();
+        assert_eq!(surfaces.get(DEFAULT_SURFACE).unwrap(), &vec![0, 1]);
+    }
+
+    #[test]
+    fn check_migrate_v0_3() {
+        let (vertices, triangles) = get_square_arrays();
+        let value: Value = serde_yaml::from_str(&format!(
+            "version: v0.3.0\nnormalization:\n  direction: x\n  size: 2.0\n\
+            geometry:\n  vertices: {}\n  triangles: {}\n",
+            vertices, triangles
+        ))
+        .unwrap();
+
+        let (migrated, report) = migrate_lnas(value).unwrap();
+        check_square_geometry(&migrated);
+        assert_eq!(report.added_surface, Some(DEFAULT_SURFACE.to_string()));
+        assert_eq!(report.dropped_fields, ["normalization"]);
+        let surfaces = migrated.get_surfaces().unwrap();
+        assert_eq!(surfaces.get(DEFAULT_SURFACE).unwrap(), &vec![0, 1]);
+    }
+
+    #[test]
+    fn check_migrate_v0_4() {
+        let (vertices, triangles) = get_square_arrays();
+        let value: Value = serde_yaml::from_str(&format!(
+            "version: v0.4.3\nnormalization:\n  direction: x\n  size: 2.0\n\
+            geometry:\n  vertices: {}\n  triangles: {}\n\
+            surfaces:\n  lower: {}\n  upper: {}\n",
+            vertices,
+            triangles,
+            encode_bytes(&encode_u32(&vec![0]).0),
+            encode_bytes(&encode_u32(&vec![1]).0)
+        ))
+        .unwrap();
+
+        let (migrated, report) = migrate_lnas(value).unwrap();
+        check_square_geometry(&migrated);
+        assert!(report.added_surface.is_none());
+        assert_eq!(report.dropped_fields, ["normalization"]);
+        let surfaces = migrated.get_surfaces().unwrap();
+        assert_eq!(surfaces.len(), 2);
+        assert_eq!(surfaces.get("lower").unwrap(), &vec![0]);
+        assert_eq!(surfaces.get("upper").unwrap(), &vec![1]);
+    }
+
+    #[test]
+    fn check_migrate_v0_5() {
+        let (vertices, triangles) = get_square_arrays();
+        let value: Value = serde_yaml::from_str(&format!(
+            "version: v0.5.1\ngeometry:\n  vertices: {}\n  triangles: {}\n\
+            surfaces:\n  square: {}\n",
+            vertices,
+            triangles,
+            encode_bytes(&encode_u32(&vec![0, 1]).0)
+        ))
+        .unwrap();
+
+        let (migrated, report) = migrate_lnas(value).unwrap();
+        check_square_geometry(&migrated);
+        assert_eq!(report.from_version, "v0.5.1");
+        assert!(report.added_surface.is_none());
+        assert!(report.dropped_fields.is_empty());
+        let surfaces = migrated.get_surfaces().unwrap();
+        assert_eq!(surfaces.get("square").unwrap(), &vec![0, 1]);
+        assert!(migrated
+            .surfaces
+            .get("square")
+            .unwrap()
+            .attributes
+            .is_none());
+    }
+
+    #[test]
+    fn check_migrate_unknown_versions() {
+        let (vertices, triangles) = get_square_arrays();
+        let mut value: Value = serde_yaml::from_str(&format!(
+            "version: v0.5.1\ngeometry:\n  vertices: {}\n  triangles: {}\n",
+            vertices, triangles
+        ))
+        .unwrap();
+        for version in ["v0.1.0", "v1.0.0", "0.5.1", "v0.5"] {
+            value["version"] = Value::from(version);
+            assert!(migrate_lnas(value.clone()).is_err());
+        }
+        // Missing geometry
+        value["version"] = Value::from("v0.5.0");
+        value.as_mapping_mut().unwrap().remove("geometry");
+        assert!(migrate_lnas(value).is_err());
+    }
+}
@@ -0,0 +1,262 @@
+use crate::lagrangian::mesh::{
+    triangle_area, triangle_centroid, triangle_cross, triangle_normal, triangle_points,
+};
+use crate::utils::{Vec3f, Vec3u};
+use clap::ValueEnum;
+
+/// Weight of each triangle normal in its vertices normals
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum NormalWeighting {
+    /// Triangle area
+    Area,
+    /// Triangle internal angle at the vertex
+    Angle,
+}
+
+/// How triangles areas are distributed among its vertices
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum AreaMethod {
+    /// One third of the area for each vertex
+    Barycentric,
+    /// Mixed Voronoi region of each vertex (Meyer et al., 2003)
+    Voronoi,
+}
+
+/// Triangles with cross product below this, relative to their longest edge squared, have
+/// undefined angles and normal
+const MIN_RELATIVE_CROSS: f32 = 1e-6;
+
+fn zero_vec() -> Vec3f {
+    return Vec3f {
+        x: 0f32,
+        y: 0f32,
+        z: 0f32,
+    };
+}
+
+/// Internal angles of triangle at each of its points, in radians
+fn triangle_angles(points: &[Vec3f; 3]) -> [f32; 3] {
+    let mut angles = [0f32; 3];
+    for i in 0..3 {
+        let mut u = points[(i + 1) % 3] - points[i];
+        let mut v = points[(i + 2) % 3] - points[i];
+        u.normalize();
+        v.normalize();
+        angles[i] = u.dot(v).clamp(-1f32, 1f32).acos();
+    }
+    return angles;
+}
+
+/// Check if triangle has (close to) zero area, so its angles and normal are undefined
+fn is_degenerated(points: &[Vec3f; 3], cross: &Vec3f) -> bool {
+    let max_edge = (0..3)
+        .map(|i| (points[(i + 1) % 3] - points[i]).norm())
+        .fold(0f32, f32::max);
+    let norm = cross.norm();
+    return norm.is_nan() || norm <= MIN_RELATIVE_CROSS * max_edge * max_edge;
+}
+
+/// Unit normal of each vertex, from the normals of the triangles that use it.
+///
+/// Degenerated triangles are skipped and vertices not used by any other triangle have null normal.
+pub fn get_vertices_normals(
+    vertices: &Vec<Vec3f>,
+    triangles: &Vec<Vec3u>,
+    weighting: NormalWeighting,
+) -> Vec<Vec3f> {
+    let mut normals: Vec<Vec3f> = vec![zero_vec(); vertices.len()];
+    for t in triangles.iter() {
+        // Cross product norm is two times the triangle area
+        let cross = triangle_cross(vertices, t);
+        let points = triangle_points(vertices, t);
+        if is_degenerated(&points, &cross) {
+            continue;
+        }
+        let weights: [f32; 3] = match weighting {
+            NormalWeighting::Area => [1f32; 3],
+            NormalWeighting::Angle => {
+                let norm = cross.norm();
+                triangle_angles(&points).map(|a| a / norm)
+            }
+        };
+        for (v, w) in [t.x, t.y, t.z].into_iter().zip(weights) {
+            let mut weighted = cross;
+            weighted.multiply(w);
+            normals[v as usize] += weighted;
+        }
+    }
+    for n in normals.iter_mut() {
+        if n.norm() > 0f32 {
+            n.normalize();
+        }
+    }
+    return normals;
+}
+
+/// Area of each vertex, such that the sum is the total area of triangles.
+///
+/// Degenerated triangles have their area split equally, as their angles are undefined.
+pub fn get_vertices_areas(
+    vertices: &Vec<Vec3f>,
+    triangles: &Vec<Vec3u>,
+    method: AreaMethod,
+) -> Vec<f32> {
+    let mut areas: Vec<f32> = vec![0f32; vertices.len()];
+    for t in triangles.iter() {
+        let area = triangle_area(vertices, t);
+        let idxs = [t.x, t.y, t.z];
+        let vert_areas: [f32; 3] = match method {
+            AreaMethod::Barycentric => [area / 3f32; 3],
+            AreaMethod::Voronoi => {
+                let points = triangle_points(vertices, t);
+                if is_degenerated(&points, &triangle_cross(vertices, t)) {
+                    [area / 3f32; 3]
+                } else {
+                    let angles = triangle_angles(&points);
+                    match angles.iter().position(|a| *a > std::f32::consts::FRAC_PI_2) {
+                        // For obtuse triangles, Voronoi region is not inside triangle
+                        Some(obtuse) => {
+                            let mut vert_areas = [area / 4f32; 3];
+                            vert_areas[obtuse] = area / 2f32;
+                            vert_areas
+                        }
+                        None => {
+                            let mut vert_areas = [0f32; 3];
+                            for i in 0..3 {
+                                let (j, k) = ((i + 1) % 3, (i + 2) % 3);
+                                let len_ij = (points[j] - points[i]).norm();
+                                let len_ik = (points[k] - points[i]).norm();
+                                vert_areas[i] = (len_ij * len_ij / angles[k].tan()
+                                    + len_ik * len_ik / angles[j].tan())
+                                    / 8f32;
+                            }
+                            vert_areas
+                        }
+                    }
+                }
+            }
+        };
+        for (v, a) in idxs.into_iter().zip(vert_areas) {
+            areas[v as usize] += a;
+        }
+    }
+    return areas;
+}
+
+/// Centroid of each triangle, used as node when forcing is applied in triangles
+pub fn get_triangles_centroids(vertices: &Vec<Vec3f>, triangles: &Vec<Vec3u>) -> Vec<Vec3f> {
+    return triangles
+        .iter()
+        .map(|t| triangle_centroid(vertices, t))
+        .collect();
+}
+
+pub fn get_triangles_areas(vertices: &Vec<Vec3f>, triangles: &Vec<Vec3u>) -> Vec<f32> {
+    return triangles
+        .iter()
+        .map(|t| triangle_area(vertices, t))
+        .collect();
+}
+
+/// Unit normal of each triangle, following its points order (right hand rule)
+pub fn get_triangles_normals(vertices: &Vec<Vec3f>, triangles: &Vec<Vec3u>) -> Vec<Vec3f> {
+    return triangles
+        .iter()
+        .map(|t| triangle_normal(vertices, t))
+        .collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_almost_equal;
+    use crate::lagrangian::test_utils::get_joined_stl;
+
+    #[test]
+    fn check_vertices_areas_sum() {
+        let (vertices, triangles) = get_joined_stl("sphere");
+        let total_area: f32 = triangles.iter().map(|t| triangle_area(&vertices, t)).sum();
+        for method in [AreaMethod::Barycentric, AreaMethod::Voronoi] {
+            let areas = get_vertices_areas(&vertices, &triangles, method);
+            let areas_sum: f32 = areas.iter().sum();
+            assert_almost_equal!(areas_sum, total_area, 1e-4 * total_area);
+            assert!(areas.iter().all(|a| *a > 0f32));
+        }
+    }
+
+    #[test]
+    fn check_vertices_normals_sphere() {
+        let (vertices, triangles) = get_joined_stl("sphere");
+        let mut center = vertices.iter().fold(zero_vec(), |acc, v| acc + *v);
+        center.divide(vertices.len() as f32);
+        for weighting in [NormalWeighting::Area, NormalWeighting::Angle] {
+            let normals = get_vertices_normals(&vertices, &triangles, weighting);
+            for (v, n) in vertices.iter().zip(normals.iter()) {
+                assert_almost_equal!(n.norm(), 1f32, 1e-5);
+                let mut radial = *v - center;
+                radial.normalize();
+                // Normals point outwards
+                assert!(n.dot(radial) > 0.9);
+            }
+        }
+    }
+
+    #[test]
+    fn check_degenerated_triangle_skipped() {
+        let (mut vertices, mut triangles) = get_joined_stl("sphere");
+        let normals = get_vertices_normals(&vertices, &triangles, NormalWeighting::Angle);
+        // Zero area triangle, with a new vertex in the middle of an edge
+        let t = triangles[0];
+        let mut middle = vertices[t.x as usize] + vertices[t.y as usize];
+        middle.divide(2f32);
+        vertices.push(middle);
+        let middle_idx = vertices.len() as u32 - 1;
+        triangles.push(Vec3u {
+            x: t.x,
+            y: t.y,
+            z: middle_idx,
+        });
+
+        for weighting in [NormalWeighting::Area, NormalWeighting::Angle] {
+            let degenerated_normals = get_vertices_normals(&vertices, &triangles, weighting);
+            assert!(degenerated_normals.iter().all(|n| n.norm().is_finite()));
+            assert_eq!(degenerated_normals[middle_idx as usize].norm(), 0f32);
+        }
+        let degenerated_normals =
+            get_vertices_normals(&vertices, &triangles, NormalWeighting::Angle);
+        assert_eq!(degenerated_normals[..normals.len()], normals);
+        let areas = get_vertices_areas(&vertices, &triangles, AreaMethod::Voronoi);
+        assert!(areas.iter().all(|a| a.is_finite()));
+    }
+
+    #[test]
+    fn check_triangles_properties_cube() {
+        let (vertices, triangles) = get_joined_stl("cube");
+        let centroids = get_triangles_centroids(&vertices, &triangles);
+        let areas = get_triangles_areas(&vertices, &triangles);
+        let normals = get_triangles_normals(&vertices, &triangles);
+        assert_eq!(centroids.len(), triangles.len());
+
+        let mut center = vertices.iter().fold(zero_vec(), |acc, v| acc + *v);
+        center.divide(vertices.len() as f32);
+        for i in 0..triangles.len() {
+            // All cube triangles have same area and normals point outwards
+            assert_almost_equal!(areas[i], areas[0], 1e-5 * areas[0]);
+            assert_almost_equal!(normals[i].norm(), 1f32, 1e-5);
+            assert!(normals[i].dot(centroids[i] - center) > 0f32);
+        }
+    }
+
+    #[test]
+    fn check_vertices_normals_cube() {
+        let (vertices, triangles) = get_joined_stl("cube");
+        let normals = get_vertices_normals(&vertices, &triangles, NormalWeighting::Angle);
+        // Angle weighted normals of cube corners are in the diagonal direction
+        for n in normals.iter() {
+            let abs_n = n.abs();
+            assert_almost_equal!(abs_n.x, 1f32 / 3f32.sqrt(), 1e-5);
+            assert_almost_equal!(abs_n.y, 1f32 / 3f32.sqrt(), 1e-5);
+            assert_almost_equal!(abs_n.z, 1f32 / 3f32.sqrt(), 1e-5);
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use crate::lagrangian::mesh::{
+    edge_key, get_connected_components, get_edges_triangles, triangle_directed_edges,
+    triangle_points, Edge,
+};
+use crate::utils::{Vec3f, Vec3u};
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OrientationReport {
+    pub n_flipped: usize,
+    pub n_components: usize,
+    /// Components without boundary, which were oriented with normals pointing outwards
+    pub n_closed_components: usize,
+}
+
+impl fmt::Display for OrientationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(
+            f,
+            "Flipped {} triangles to orient {} components ({} closed)",
+            self.n_flipped, self.n_components, self.n_closed_components
+        );
+    }
+}
+
+/// Reverse triangle winding, inverting its normal
+pub fn flip_triangle(triangle: &Vec3u) -> Vec3u {
+    return Vec3u {
+        x: triangle.x,
+        y: triangle.z,
+        z: triangle.y,
+    };
+}
+
+fn oriented_triangle(triangle: &Vec3u, flip: bool) -> Vec3u {
+    if flip {
+        return flip_triangle(triangle);
+    }
+    return *triangle;
+}
+
+fn traverses_edge(triangle: &Vec3u, v0: u32, v1: u32) -> bool {
+    return triangle_directed_edges(triangle).contains(&(v0, v1));
+}
+
+/// Signed volume enclosed by triangles, positive when normals point outwards
+pub fn get_signed_volume(vertices: &Vec<Vec3f>, triangles: &Vec<Vec3u>) -> f32 {
+    let mut volume = 0f64;
+    for t in triangles.iter() {
+        let [p0, p1, p2] = triangle_points(vertices, t);
+        volume += p0.dot(p1.cross(p2)) as f64;
+    }
+    return (volume / 6f64) as f32;
+}
+
+/// Propagate orientation from first triangle of component through its manifold edges.
+///
+/// Returns which triangles must be flipped to agree with the first one
+fn propagate_orientation(
+    component: &Vec<usize>,
+    triangles: &Vec<Vec3u>,
+    edges_triangles: &HashMap<Edge, Vec<usize>>,
+    flips: &mut HashMap<usize, bool>,
+) {
+    let mut stack: Vec<usize> = vec![component[0]];
+    flips.insert(component[0], false);
+    while let Some(t_idx) = stack.pop() {
+        let triangle = oriented_triangle(&triangles[t_idx], flips[&t_idx]);
+        for (v0, v1) in triangle_directed_edges(&triangle) {
+            let neighbours = edges_triangles.get(&edge_key(v0, v1)).unwrap();
+            if neighbours.len() != 2 {
+                continue;
+            }
+            for n_idx in neighbours.iter() {
+                if *n_idx == t_idx || flips.contains_key(n_idx) {
+                    continue;
+                }
+                // Consistent neighbours traverse the shared edge in opposite directions
+                flips.insert(*n_idx, traverses_edge(&triangles[*n_idx], v0, v1));
+                stack.push(*n_idx);
+            }
+        }
+    }
+}
+
+/// Make triangles winding consistent in each edge connected component.
+///
+/// In open components the orientation that requires less flips is kept, closed
+/// components are oriented with normals pointing outwards (positive signed volume).
+pub fn orient_triangles(vertices: &Vec<Vec3f>, triangles: &mut Vec<Vec3u>) -> OrientationReport {
+    let edges_triangles = get_edges_triangles(triangles);
+    let components = get_connected_components(triangles, &edges_triangles);
+
+    let mut n_flipped = 0;
+    let mut n_closed_components = 0;
+    for component in components.iter() {
+        let mut flips: HashMap<usize, bool> = HashMap::with_capacity(component.len());
+        propagate_orientation(component, triangles, &edges_triangles, &mut flips);
+
+        let is_closed = component.iter().all(|t_idx| {
+            triangle_directed_edges(&triangles[*t_idx])
+                .iter()
+                .all(|(v0, v1)| edges_triangles.get(&edge_key(*v0, *v1)).unwrap().len() == 2)
+        });
+        let invert_component = if is_closed {
+            n_closed_components += 1;
+            let oriented: Vec<Vec3u> = component
+                .iter()
+                .map(|t_idx| oriented_triangle(&triangles[*t_idx], flips[t_idx]))
+                .collect();
+            get_signed_volume(vertices, &oriented) < 0f32
+        } else {
+            let n_component_flips = flips.values().filter(|f| **f).count();
+            2 * n_component_flips > component.len()
+        };
+
+        for t_idx in component.iter() {
+            if flips[t_idx] != invert_component {
+                triangles[*t_idx] = flip_triangle(&triangles[*t_idx]);
+                n_flipped += 1;
+            }
+        }
+    }
+
+    return OrientationReport {
+        n_flipped,
+        n_components: components.len(),
+        n_closed_components,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lagrangian::quality::analyze_mesh;
+    use crate::lagrangian::test_utils::get_joined_stl;
+
+    #[test]
+    fn check_orientation_keeps_stl_cube() {
+        let (vertices, mut triangles) = get_joined_stl("cube");
+        let orig_triangles = triangles.clone();
+        let report = orient_triangles(&vertices, &mut triangles);
+
+        assert_eq!(report.n_flipped, 0);
+        assert_eq!(report.n_closed_components, 1);
+        assert!(orig_triangles == triangles);
+        assert!(get_signed_volume(&vertices, &triangles) > 0f32);
+    }
+
+    #[test]
+    fn check_orientation_fixes_flipped_triangle() {
+        let (vertices, mut triangles) = get_joined_stl("cube");
+        triangles[3] = flip_triangle(&triangles[3]);
+        let report = orient_triangles(&vertices, &mut triangles);
+
+        assert_eq!(report.n_flipped, 1);
+        assert!(analyze_mesh(&vertices, &triangles).is_consistently_oriented());
+    }
+
+    #[test]
+    fn check_orientation_inverted_stl_cube() {
+        let (vertices, triangles) = get_joined_stl("cube");
+        let mut inverted_triangles: Vec<Vec3u> = triangles.iter().map(flip_triangle).collect();
+        let report = orient_triangles(&vertices, &mut inverted_triangles);
+
+        assert_eq!(report.n_flipped, triangles.len());
+        assert!(inverted_triangles == triangles);
+    }
+
+    #[test]
+    fn check_orientation_stl_plane() {
+        let (vertices, mut triangles) = get_joined_stl("plane");
+        triangles[0] = flip_triangle(&triangles[0]);
+        let report = orient_triangles(&vertices, &mut triangles);
+
+        assert_eq!(report.n_flipped, 1);
+        assert_eq!(report.n_closed_components, 0);
+        assert!(analyze_mesh(&vertices, &triangles).is_consistently_oriented());
+    }
+}
@@ -0,0 +1,245 @@
+use crate::lagrangian::mesh::triangle_points;
+use crate::utils::{Vec3f, Vec3u};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// Area of geometry shadow in plane normal to a direction, considering occlusion
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProjectedAreaReport {
+    /// Unit projection direction
+    pub direction: Vec3f,
+    /// Number of cells along largest side of projection
+    pub resolution: usize,
+    /// Side of raster cells
+    pub cell_size: f32,
+    /// Projected area of all triangles together
+    pub total: f32,
+    /// Projected area of each surface alone
+    pub surfaces: BTreeMap<String, f32>,
+}
+
+impl fmt::Display for ProjectedAreaReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Projected area along {} (resolution {}, cell size {:.4e}): {:.4e}",
+            self.direction, self.resolution, self.cell_size, self.total
+        )?;
+        for (surface_name, area) in self.surfaces.iter() {
+            write!(f, "\n  {}: {:.4e}", surface_name, area)?;
+        }
+        return Ok(());
+    }
+}
+
+/// Orthonormal vectors perpendicular to unit `direction`
+fn get_projection_basis(direction: Vec3f) -> (Vec3f, Vec3f) {
+    let abs_dir = direction.abs();
+    // Use axis least aligned with direction to build basis
+    let axis = if abs_dir.x <= abs_dir.y && abs_dir.x <= abs_dir.z {
+        Vec3f {
+            x: 1f32,
+            y: 0f32,
+            z: 0f32,
+        }
+    } else if abs_dir.y <= abs_dir.z {
+        Vec3f {
+            x: 0f32,
+            y: 1f32,
+            z: 0f32,
+        }
+    } else {
+        Vec3f {
+            x: 0f32,
+            y: 0f32,
+            z: 1f32,
+        }
+    };
+    let mut u = direction.cross(axis);
+    u.normalize();
+    let v = direction.cross(u);
+    return (u, v);
+}
+
+/// Raster of cells covered by projected triangles
+struct Raster {
+    origin: (f32, f32),
+    cell_size: f32,
+    nx: usize,
+    ny: usize,
+}
+
+impl Raster {
+    fn new(points: &Vec<(f32, f32)>, resolution: usize) -> Raster {
+        let (mut min_x, mut min_y) = (f32::MAX, f32::MAX);
+        let (mut max_x, mut max_y) = (f32::MIN, f32::MIN);
+        for (x, y) in points.iter() {
+            min_x = min_x.min(*x);
+            min_y = min_y.min(*y);
+            max_x = max_x.max(*x);
+            max_y = max_y.max(*y);
+        }
+        let max_side = (max_x - min_x).max(max_y - min_y);
+        let cell_size = match max_side > 0f32 {
+            true => max_side / resolution as f32,
+            false => 1f32,
+        };
+        return Raster {
+            origin: (min_x, min_y),
+            cell_size,
+            nx: (((max_x - min_x) / cell_size).ceil() as usize).max(1),
+            ny: (((max_y - min_y) / cell_size).ceil() as usize).max(1),
+        };
+    }
+
+    /// Mark cells which centers are inside projected triangle
+    fn fill_triangle(&self, covered: &mut [bool], triangle: [(f32, f32); 3]) {
+        let [a, b, c] = triangle;
+        let signed_area = (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0);
+        if signed_area == 0f32 {
+            return;
+        }
+        // Small tolerance, so centers on shared edges are not missed
+        let tol = -1e-6 * signed_area.abs();
+        let edge_function = |p: (f32, f32), q: (f32, f32), r: (f32, f32)| {
+            return ((q.0 - p.0) * (r.1 - p.1) - (q.1 - p.1) * (r.0 - p.0)) * signed_area.signum();
+        };
+
+        let to_cell = |value: f32, origin: f32, n: usize| {
+            return (((value - origin) / self.cell_size).max(0f32) as usize).min(n - 1);
+        };
+        let i_min = to_cell(a.0.min(b.0).min(c.0), self.origin.0, self.nx);
+        let i_max = to_cell(a.0.max(b.0).max(c.0), self.origin.0, self.nx);
+        let j_min = to_cell(a.1.min(b.1).min(c.1), self.origin.1, self.ny);
+        let j_max = to_cell(a.1.max(b.1).max(c.1), self.origin.1, self.ny);
+        for j in j_min..=j_max {
+            for i in i_min..=i_max {
+                let center = (
+                    self.origin.0 + (i as f32 + 0.5) * self.cell_size,
+                    self.origin.1 + (j as f32 + 0.5) * self.cell_size,
+                );
+                if edge_function(a, b, center) >= tol
+                    && edge_function(b, c, center) >= tol
+                    && edge_function(c, a, center) >= tol
+                {
+                    covered[j * self.nx + i] = true;
+                }
+            }
+        }
+    }
+
+    fn get_area(&self, triangles: &Vec<[(f32, f32); 3]>) -> f32 {
+        let mut covered: Vec<bool> = vec![false; self.nx * self.ny];
+        for t in triangles.iter() {
+            self.fill_triangle(&mut covered, *t);
+        }
+        let n_covered = covered.iter().filter(|c| **c).count();
+        return n_covered as f32 * self.cell_size * self.cell_size;
+    }
+}
+
+/// Projected area along `direction`, by rasterizing triangles in the plane normal to it.
+///
+/// `resolution` is the number of cells along the largest side of the whole geometry projection.
+pub fn get_projected_area(
+    vertices: &Vec<Vec3f>,
+    triangles: &Vec<Vec3u>,
+    surfaces: &HashMap<String, Vec<u32>>,
+    direction: Vec3f,
+    resolution: usize,
+) -> ProjectedAreaReport {
+    let mut direction = direction;
+    direction.normalize();
+    let (u, v) = get_projection_basis(direction);
+    let projected_vertices: Vec<(f32, f32)> =
+        vertices.iter().map(|p| (p.dot(u), p.dot(v))).collect();
+    let projected_triangles: Vec<[(f32, f32); 3]> = triangles
+        .iter()
+        .map(|t| {
+            let points = triangle_points(vertices, t);
+            points.map(|p| (p.dot(u), p.dot(v)))
+        })
+        .collect();
+
+    let raster = Raster::new(&projected_vertices, resolution);
+    let mut surfaces_areas: BTreeMap<String, f32> = BTreeMap::new();
+    for (surface_name, triangles_idxs) in surfaces.iter() {
+        let surface_triangles: Vec<[(f32, f32); 3]> = triangles_idxs
+            .iter()
+            .map(|t_idx| projected_triangles[*t_idx as usize])
+            .collect();
+        surfaces_areas.insert(surface_name.to_owned(), raster.get_area(&surface_triangles));
+    }
+
+    return ProjectedAreaReport {
+        direction,
+        resolution,
+        cell_size: raster.cell_size,
+        total: raster.get_area(&projected_triangles),
+        surfaces: surfaces_areas,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_almost_equal;
+    use crate::lagrangian::test_utils::get_joined_stls;
+
+    #[test]
+    fn check_projected_area_cube() {
+        let (vertices, triangles, surfaces) = get_joined_stls(&["cube"]);
+        let side = 20f32;
+        let x_dir = Vec3f {
+            x: 1f32,
+            y: 0f32,
+            z: 0f32,
+        };
+        let report = get_projected_area(&vertices, &triangles, &surfaces, x_dir, 200);
+        assert_almost_equal!(report.total, side * side, 1e-3 * side * side);
+        assert_eq!(report.surfaces.get("cube").unwrap(), &report.total);
+
+        // Cube seen from its edge is a rectangle with the face diagonal as a side
+        let xy_dir = Vec3f {
+            x: 1f32,
+            y: 1f32,
+            z: 0f32,
+        };
+        let report = get_projected_area(&vertices, &triangles, &surfaces, xy_dir, 1000);
+        let expected = 2f32.sqrt() * side * side;
+        assert_almost_equal!(report.total, expected, 1e-2 * expected);
+    }
+
+    #[test]
+    fn check_projected_area_sphere() {
+        let (vertices, triangles, surfaces) = get_joined_stls(&["sphere"]);
+        let radius = vertices.iter().map(|v| v.x).fold(f32::MIN, f32::max)
+            - vertices.iter().map(|v| v.x).fold(f32::MAX, f32::min);
+        let radius = radius / 2f32;
+        let direction = Vec3f {
+            x: 1f32,
+            y: -2f32,
+            z: 3f32,
+        };
+        let report = get_projected_area(&vertices, &triangles, &surfaces, direction, 500);
+        // Tessellated sphere shadow is slightly smaller than the circle
+        let circle_area = std::f32::consts::PI * radius * radius;
+        assert!(report.total < circle_area);
+        assert_almost_equal!(report.total, circle_area, 3e-2 * circle_area);
+    }
+
+    #[test]
+    fn check_projected_area_occlusion() {
+        let (vertices, triangles, surfaces) = get_joined_stls(&["cube", "sphere"]);
+        let direction = Vec3f {
+            x: 0f32,
+            y: 0f32,
+            z: 1f32,
+        };
+        let report = get_projected_area(&vertices, &triangles, &surfaces, direction, 500);
+        let sum_surfaces: f32 = report.surfaces.values().sum();
+        let max_surface = report.surfaces.values().cloned().fold(0f32, f32::max);
+        assert!(report.total <= sum_surfaces * (1f32 + 1e-5));
+        assert!(report.total >= max_surface * (1f32 - 1e-5));
+    }
+}
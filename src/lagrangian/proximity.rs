@@ -0,0 +1,383 @@
+use crate::lagrangian::bvh::{Aabb, Bvh};
+use crate::lagrangian::intersection::triangles_intersection_points;
+use crate::lagrangian::mesh::{
+    get_connected_components, get_edges_triangles, triangle_area, triangle_centroid,
+    triangle_points,
+};
+use crate::utils::{Vec3f, Vec3u};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Connected triangles of a surface closer than threshold to another surface
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProximityRegion {
+    pub surface_name: String,
+    pub n_triangles: usize,
+    pub area: f32,
+    /// Area weighted centroid of region triangles
+    pub centroid: Vec3f,
+    pub min_distance: f32,
+}
+
+impl fmt::Display for ProximityRegion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(
+            f,
+            "region of '{}' at {} with {} triangles (area {:.4e}), minimum distance {:.4e}",
+            self.surface_name, self.centroid, self.n_triangles, self.area, self.min_distance
+        );
+    }
+}
+
+/// Proximity between two surfaces
+#[derive(Clone, Debug, PartialEq)]
+pub struct SurfacesProximity {
+    pub surfaces: (String, String),
+    pub min_distance: f32,
+    /// Regions of both surfaces closer than threshold to the other
+    pub regions: Vec<ProximityRegion>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProximityReport {
+    pub threshold: f32,
+    pub pairs: Vec<SurfacesProximity>,
+}
+
+impl ProximityReport {
+    /// Pairs of surfaces closer than threshold
+    pub fn close_pairs(&self) -> Vec<&SurfacesProximity> {
+        return self
+            .pairs
+            .iter()
+            .filter(|p| p.min_distance < self.threshold)
+            .collect();
+    }
+}
+
+impl fmt::Display for ProximityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Surfaces proximity (threshold {}): {} of {} pairs below threshold",
+            self.threshold,
+            self.close_pairs().len(),
+            self.pairs.len()
+        )?;
+        for pair in self.pairs.iter() {
+            write!(
+                f,
+                "\n  '{}' and '{}': minimum distance {:.4e}",
+                pair.surfaces.0, pair.surfaces.1, pair.min_distance
+            )?;
+            for region in pair.regions.iter() {
+                write!(f, "\n    {}", region)?;
+            }
+        }
+        return Ok(());
+    }
+}
+
+/// Closest point of triangle `[a, b, c]` to `p` (Ericson, Real-Time Collision Detection)
+fn closest_point_triangle(p: Vec3f, triangle: &[Vec3f; 3]) -> Vec3f {
+    let [a, b, c] = *triangle;
+    let scaled = |v: Vec3f, s: f32| {
+        let mut v = v;
+        v.multiply(s);
+        return v;
+    };
+    let ab = b - a;
+    let ac = c - a;
+    let ap = p - a;
+    let d1 = ab.dot(ap);
+    let d2 = ac.dot(ap);
+    if d1 <= 0f32 && d2 <= 0f32 {
+        return a;
+    }
+    let bp = p - b;
+    let d3 = ab.dot(bp);
+    let d4 = ac.dot(bp);
+    if d3 >= 0f32 && d4 <= d3 {
+        return b;
+    }
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0f32 && d1 >= 0f32 && d3 <= 0f32 {
+        return a + scaled(ab, d1 / (d1 - d3));
+    }
+    let cp = p - c;
+    let d5 = ab.dot(cp);
+    let d6 = ac.dot(cp);
+    if d6 >= 0f32 && d5 <= d6 {
+        return c;
+    }
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0f32 && d2 >= 0f32 && d6 <= 0f32 {
+        return a + scaled(ac, d2 / (d2 - d6));
+    }
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0f32 && (d4 - d3) >= 0f32 && (d5 - d6) >= 0f32 {
+        return b + scaled(c - b, (d4 - d3) / ((d4 - d3) + (d5 - d6)));
+    }
+    let denom = 1f32 / (va + vb + vc);
+    return a + scaled(ab, vb * denom) + scaled(ac, vc * denom);
+}
+
+/// Distance between segments `p0`-`p1` and `q0`-`q1`
+fn segments_distance(p0: Vec3f, p1: Vec3f, q0: Vec3f, q1: Vec3f) -> f32 {
+    let d1 = p1 - p0;
+    let d2 = q1 - q0;
+    let r = p0 - q0;
+    let a = d1.dot(d1);
+    let e = d2.dot(d2);
+    let f = d2.dot(r);
+    let (s, t) = if a <= f32::EPSILON && e <= f32::EPSILON {
+        (0f32, 0f32)
+    } else if a <= f32::EPSILON {
+        (0f32, (f / e).clamp(0f32, 1f32))
+    } else {
+        let c = d1.dot(r);
+        if e <= f32::EPSILON {
+            ((-c / a).clamp(0f32, 1f32), 0f32)
+        } else {
+            let b = d1.dot(d2);
+            let denom = a * e - b * b;
+            let mut s = match denom > 0f32 {
+                true => ((b * f - c * e) / denom).clamp(0f32, 1f32),
+                false => 0f32,
+            };
+            let mut t = (b * s + f) / e;
+            if t < 0f32 {
+                t = 0f32;
+                s = (-c / a).clamp(0f32, 1f32);
+            } else if t > 1f32 {
+                t = 1f32;
+                s = ((b - c) / a).clamp(0f32, 1f32);
+            }
+            (s, t)
+        }
+    };
+    let mut closest_p = d1;
+    closest_p.multiply(s);
+    let mut closest_q = d2;
+    closest_q.multiply(t);
+    return ((p0 + closest_p) - (q0 + closest_q)).norm();
+}
+
+/// Minimum distance between two triangles
+pub fn triangles_distance(t0: &[Vec3f; 3], t1: &[Vec3f; 3]) -> f32 {
+    if !triangles_intersection_points(t0, t1).is_empty() {
+        return 0f32;
+    }
+    let mut distance = f32::MAX;
+    for (points, other) in [(t0, t1), (t1, t0)] {
+        for p in points.iter() {
+            distance = distance.min((closest_point_triangle(*p, other) - *p).norm());
+        }
+    }
+    for i in 0..3 {
+        for j in 0..3 {
+            distance = distance.min(segments_distance(
+                t0[i],
+                t0[(i + 1) % 3],
+                t1[j],
+                t1[(j + 1) % 3],
+            ));
+        }
+    }
+    return distance;
+}
+
+/// Triangles of a surface, with BVH for distance queries
+struct SurfaceMesh {
+    name: String,
+    triangles_idxs: Vec<u32>,
+    bvh: Bvh,
+}
+
+impl SurfaceMesh {
+    fn new(
+        name: &str,
+        triangles_idxs: &Vec<u32>,
+        vertices: &Vec<Vec3f>,
+        triangles: &Vec<Vec3u>,
+    ) -> SurfaceMesh {
+        let aabbs: Vec<Aabb> = triangles_idxs
+            .iter()
+            .map(|t_idx| Aabb::from_points(&triangle_points(vertices, &triangles[*t_idx as usize])))
+            .collect();
+        return SurfaceMesh {
+            name: name.to_string(),
+            triangles_idxs: triangles_idxs.clone(),
+            bvh: Bvh::new(aabbs),
+        };
+    }
+
+    /// Distance of each triangle to `other` surface, when below `max_distance`
+    fn distances_to(
+        &self,
+        other: &SurfaceMesh,
+        vertices: &Vec<Vec3f>,
+        triangles: &Vec<Vec3u>,
+        max_distance: f32,
+    ) -> Vec<Option<f32>> {
+        return self
+            .triangles_idxs
+            .iter()
+            .enumerate()
+            .map(|(item, t_idx)| {
+                let points = triangle_points(vertices, &triangles[*t_idx as usize]);
+                other
+                    .bvh
+                    .nearest(self.bvh.item_aabb(item), max_distance, |other_item| {
+                        let other_t = &triangles[other.triangles_idxs[other_item] as usize];
+                        triangles_distance(&points, &triangle_points(vertices, other_t))
+                    })
+                    .map(|(_, d)| d)
+            })
+            .collect();
+    }
+
+    /// Group triangles closer than threshold in edge connected regions
+    fn get_regions(
+        &self,
+        distances: &Vec<Option<f32>>,
+        threshold: f32,
+        vertices: &Vec<Vec3f>,
+        triangles: &Vec<Vec3u>,
+    ) -> Vec<ProximityRegion> {
+        let close_items: Vec<usize> = (0..distances.len())
+            .filter(|item| distances[*item].is_some_and(|d| d < threshold))
+            .collect();
+        let close_triangles: Vec<Vec3u> = close_items
+            .iter()
+            .map(|item| triangles[self.triangles_idxs[*item] as usize])
+            .collect();
+        let edges_triangles = get_edges_triangles(&close_triangles);
+        let components = get_connected_components(&close_triangles, &edges_triangles);
+        return components
+            .iter()
+            .map(|component| {
+                let mut area = 0f32;
+                let mut centroid = Vec3f {
+                    x: 0f32,
+                    y: 0f32,
+                    z: 0f32,
+                };
+                let mut min_distance = f32::MAX;
+                for local_idx in component.iter() {
+                    let t = &close_triangles[*local_idx];
+                    let t_area = triangle_area(vertices, t);
+                    let mut t_centroid = triangle_centroid(vertices, t);
+                    t_centroid.multiply(t_area);
+                    centroid += t_centroid;
+                    area += t_area;
+                    min_distance = min_distance.min(distances[close_items[*local_idx]].unwrap());
+                }
+                if area > 0f32 {
+                    centroid.divide(area);
+                }
+                ProximityRegion {
+                    surface_name: self.name.clone(),
+                    n_triangles: component.len(),
+                    area,
+                    centroid,
+                    min_distance,
+                }
+            })
+            .collect();
+    }
+}
+
+/// Minimum distance between each pair of surfaces and regions closer than `threshold`.
+///
+/// Surfaces connected to each other (sharing vertices) have null distance.
+pub fn get_surfaces_proximity(
+    vertices: &Vec<Vec3f>,
+    triangles: &Vec<Vec3u>,
+    surfaces: &HashMap<String, Vec<u32>>,
+    threshold: f32,
+) -> ProximityReport {
+    let mut surface_names: Vec<&String> = surfaces.keys().collect();
+    surface_names.sort();
+    let meshes: Vec<SurfaceMesh> = surface_names
+        .iter()
+        .map(|s| SurfaceMesh::new(s, surfaces.get(*s).unwrap(), vertices, triangles))
+        .collect();
+
+    let mut pairs: Vec<SurfacesProximity> = Vec::new();
+    for (i, mesh0) in meshes.iter().enumerate() {
+        for mesh1 in meshes[i + 1..].iter() {
+            let distances0 = mesh0.distances_to(mesh1, vertices, triangles, f32::MAX);
+            let distances1 = mesh1.distances_to(mesh0, vertices, triangles, threshold);
+            let min_distance = distances0
+                .iter()
+                .flatten()
+                .cloned()
+                .fold(f32::MAX, f32::min);
+            let mut regions = mesh0.get_regions(&distances0, threshold, vertices, triangles);
+            regions.extend(mesh1.get_regions(&distances1, threshold, vertices, triangles));
+            pairs.push(SurfacesProximity {
+                surfaces: (mesh0.name.clone(), mesh1.name.clone()),
+                min_distance,
+                regions,
+            });
+        }
+    }
+    return ProximityReport { threshold, pairs };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_almost_equal;
+    use crate::lagrangian::test_utils::get_joined_stls;
+
+    fn vec3f(x: f32, y: f32, z: f32) -> Vec3f {
+        return Vec3f { x, y, z };
+    }
+
+    #[test]
+    fn check_triangles_distance() {
+        let t0 = [
+            vec3f(0f32, 0f32, 0f32),
+            vec3f(1f32, 0f32, 0f32),
+            vec3f(0f32, 1f32, 0f32),
+        ];
+        // Parallel triangle above
+        let t1 = t0.map(|p| p + vec3f(0f32, 0f32, 0.5));
+        assert_almost_equal!(triangles_distance(&t0, &t1), 0.5, 1e-6);
+        // Crossed edges
+        let t2 = [
+            vec3f(0.5, -1f32, 1f32),
+            vec3f(0.5, 1f32, 1f32),
+            vec3f(0.5, 0f32, 2f32),
+        ];
+        assert_almost_equal!(triangles_distance(&t0, &t2), 1f32, 1e-6);
+        // Crossing triangle
+        let t3 = t2.map(|p| p - vec3f(0f32, 0f32, 1.5));
+        assert_eq!(triangles_distance(&t0, &t3), 0f32);
+    }
+
+    #[test]
+    fn check_proximity_cube_plane() {
+        let (vertices, triangles, surfaces) = get_joined_stls(&["cube", "plane"]);
+        // Plane edge is 32 units away from cube edge in x and 40 units in y
+        let expected = (32f32 * 32f32 + 40f32 * 40f32).sqrt();
+        let report = get_surfaces_proximity(&vertices, &triangles, &surfaces, 1f32);
+        assert_eq!(report.pairs.len(), 1);
+        assert_almost_equal!(report.pairs[0].min_distance, expected, 1e-3);
+        assert!(report.close_pairs().is_empty());
+        assert!(report.pairs[0].regions.is_empty());
+
+        let report = get_surfaces_proximity(&vertices, &triangles, &surfaces, 52f32);
+        assert_eq!(report.close_pairs().len(), 1);
+        let cube_regions: Vec<&ProximityRegion> = report.pairs[0]
+            .regions
+            .iter()
+            .filter(|r| r.surface_name == "cube")
+            .collect();
+        // Cube triangles around the edge closest to plane
+        assert_eq!(cube_regions.len(), 1);
+        assert!(cube_regions[0].n_triangles >= 2);
+    }
+}
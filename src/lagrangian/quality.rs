@@ -0,0 +1,207 @@
+use crate::lagrangian::mesh::{
+    edge_key, get_connected_components, get_edges_triangles, triangle_directed_edges, Edge,
+};
+use crate::utils::{Vec3f, Vec3u};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// Topological quality of a joined mesh
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MeshQuality {
+    pub n_vertices: usize,
+    pub n_triangles: usize,
+    pub n_edges: usize,
+    /// Edges used by a single triangle
+    pub boundary_edges: Vec<Edge>,
+    /// Edges shared by more than two triangles
+    pub non_manifold_edges: Vec<Edge>,
+    /// Vertices whose triangles fan is split in more than one group (e.g. bowtie)
+    pub non_manifold_vertices: Vec<u32>,
+    /// Edges shared by two triangles that traverse it in the same direction
+    pub inconsistent_edges: Vec<Edge>,
+    pub n_components: usize,
+    /// V - E + F, considering only the vertices used by triangles
+    pub euler_characteristic: i64,
+}
+
+impl MeshQuality {
+    pub fn is_manifold(&self) -> bool {
+        return self.non_manifold_edges.is_empty() && self.non_manifold_vertices.is_empty();
+    }
+
+    pub fn is_watertight(&self) -> bool {
+        return self.boundary_edges.is_empty() && self.is_manifold();
+    }
+
+    pub fn is_consistently_oriented(&self) -> bool {
+        return self.inconsistent_edges.is_empty();
+    }
+}
+
+impl fmt::Display for MeshQuality {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Mesh with {} vertices, {} triangles and {} edges",
+            self.n_vertices, self.n_triangles, self.n_edges
+        )?;
+        writeln!(f, "  Watertight: {}", self.is_watertight())?;
+        writeln!(f, "  Boundary edges: {}", self.boundary_edges.len())?;
+        writeln!(f, "  Non-manifold edges: {}", self.non_manifold_edges.len())?;
+        writeln!(
+            f,
+            "  Non-manifold vertices: {}",
+            self.non_manifold_vertices.len()
+        )?;
+        writeln!(
+            f,
+            "  Inconsistently oriented edges: {}",
+            self.inconsistent_edges.len()
+        )?;
+        writeln!(f, "  Connected components: {}", self.n_components)?;
+        return write!(f, "  Euler characteristic: {}", self.euler_characteristic);
+    }
+}
+
+fn get_vertices_triangles(triangles: &Vec<Vec3u>) -> HashMap<u32, Vec<usize>> {
+    let mut vertices_triangles: HashMap<u32, Vec<usize>> = HashMap::new();
+    for (t_idx, t) in triangles.iter().enumerate() {
+        for v in [t.x, t.y, t.z] {
+            vertices_triangles.entry(v).or_default().push(t_idx);
+        }
+    }
+    return vertices_triangles;
+}
+
+/// Check if triangles around vertex are connected by edges that include the vertex
+fn is_vertex_fan_connected(
+    vertex: u32,
+    vertex_triangles: &Vec<usize>,
+    triangles: &Vec<Vec3u>,
+    edges_triangles: &HashMap<Edge, Vec<usize>>,
+) -> bool {
+    let fan: HashSet<usize> = vertex_triangles.iter().cloned().collect();
+    let mut visited: HashSet<usize> = HashSet::new();
+    let mut stack: Vec<usize> = vec![vertex_triangles[0]];
+    visited.insert(vertex_triangles[0]);
+    while let Some(t_idx) = stack.pop() {
+        for (v0, v1) in triangle_directed_edges(&triangles[t_idx]) {
+            if v0 != vertex && v1 != vertex {
+                continue;
+            }
+            for n_idx in edges_triangles.get(&edge_key(v0, v1)).unwrap().iter() {
+                if fan.contains(n_idx) && visited.insert(*n_idx) {
+                    stack.push(*n_idx);
+                }
+            }
+        }
+    }
+    return visited.len() == fan.len();
+}
+
+/// Analyze topology of joined mesh (as generated by `join_information`)
+pub fn analyze_mesh(vertices: &Vec<Vec3f>, triangles: &Vec<Vec3u>) -> MeshQuality {
+    let edges_triangles = get_edges_triangles(triangles);
+
+    let mut boundary_edges: Vec<Edge> = Vec::new();
+    let mut non_manifold_edges: Vec<Edge> = Vec::new();
+    let mut inconsistent_edges: Vec<Edge> = Vec::new();
+    for (edge, edge_triangles) in edges_triangles.iter() {
+        match edge_triangles.len() {
+            1 => boundary_edges.push(*edge),
+            2 => {
+                // Neighbours are consistent when they traverse the edge in opposite directions
+                let directions: Vec<bool> = edge_triangles
+                    .iter()
+                    .map(|t_idx| triangle_directed_edges(&triangles[*t_idx]).contains(edge))
+                    .collect();
+                if directions[0] == directions[1] {
+                    inconsistent_edges.push(*edge);
+                }
+            }
+            _ => non_manifold_edges.push(*edge),
+        }
+    }
+    boundary_edges.sort();
+    non_manifold_edges.sort();
+    inconsistent_edges.sort();
+
+    let vertices_triangles = get_vertices_triangles(triangles);
+    let mut non_manifold_vertices: Vec<u32> = vertices_triangles
+        .iter()
+        .filter(|(v, v_triangles)| {
+            !is_vertex_fan_connected(**v, v_triangles, triangles, &edges_triangles)
+        })
+        .map(|(v, _)| *v)
+        .collect();
+    non_manifold_vertices.sort();
+
+    let components = get_connected_components(triangles, &edges_triangles);
+    let euler_characteristic =
+        vertices_triangles.len() as i64 - edges_triangles.len() as i64 + triangles.len() as i64;
+
+    return MeshQuality {
+        n_vertices: vertices.len(),
+        n_triangles: triangles.len(),
+        n_edges: edges_triangles.len(),
+        boundary_edges,
+        non_manifold_edges,
+        non_manifold_vertices,
+        inconsistent_edges,
+        n_components: components.len(),
+        euler_characteristic,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lagrangian::test_utils::get_joined_stl;
+
+    #[test]
+    fn check_quality_stl_cube() {
+        let (vertices, triangles) = get_joined_stl("cube");
+        let quality = analyze_mesh(&vertices, &triangles);
+
+        assert!(quality.is_watertight());
+        assert!(quality.is_consistently_oriented());
+        assert_eq!(quality.n_components, 1);
+        assert_eq!(quality.euler_characteristic, 2);
+    }
+
+    #[test]
+    fn check_quality_stl_cube_with_hole() {
+        let (vertices, mut triangles) = get_joined_stl("cube");
+        triangles.pop();
+        let quality = analyze_mesh(&vertices, &triangles);
+
+        assert!(!quality.is_watertight());
+        assert_eq!(quality.boundary_edges.len(), 3);
+        assert_eq!(quality.euler_characteristic, 1);
+    }
+
+    #[test]
+    fn check_quality_inconsistent_orientation() {
+        let (vertices, mut triangles) = get_joined_stl("cube");
+        let t = triangles[0];
+        triangles[0] = Vec3u {
+            x: t.x,
+            y: t.z,
+            z: t.y,
+        };
+        let quality = analyze_mesh(&vertices, &triangles);
+
+        assert!(quality.is_watertight());
+        assert_eq!(quality.inconsistent_edges.len(), 3);
+    }
+
+    #[test]
+    fn check_quality_stl_plane() {
+        let (vertices, triangles) = get_joined_stl("plane");
+        let quality = analyze_mesh(&vertices, &triangles);
+
+        assert!(!quality.is_watertight());
+        assert!(quality.boundary_edges.len() > 0);
+        assert!(quality.is_consistently_oriented());
+    }
+}
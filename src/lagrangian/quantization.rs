@@ -0,0 +1,208 @@
+use crate::utils::Vec3f;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+
+/// Integer type of quantized vertices coordinates
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QuantizationDtype {
+    /// 16 bits per axis (65535 steps along bounding box)
+    U16,
+    /// 32 bits per axis
+    U32,
+}
+
+impl QuantizationDtype {
+    pub fn name(&self) -> &'static str {
+        return match self {
+            QuantizationDtype::U16 => "u16",
+            QuantizationDtype::U32 => "u32",
+        };
+    }
+
+    pub fn from_name(name: &str) -> Result<QuantizationDtype, Box<dyn Error>> {
+        return match name {
+            "u16" => Ok(QuantizationDtype::U16),
+            "u32" => Ok(QuantizationDtype::U32),
+            _ => Err(format!("Unknown quantization dtype '{}'", name).into()),
+        };
+    }
+
+    pub fn max_value(&self) -> f64 {
+        return match self {
+            QuantizationDtype::U16 => u16::MAX as f64,
+            QuantizationDtype::U32 => u32::MAX as f64,
+        };
+    }
+
+    pub fn n_bytes(&self) -> usize {
+        return match self {
+            QuantizationDtype::U16 => 2,
+            QuantizationDtype::U32 => 4,
+        };
+    }
+}
+
+/// Vertices quantized relative to bounding box, recovered as `offset + q * scale` in f64
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Quantization {
+    pub dtype: QuantizationDtype,
+    pub offset: [f64; 3],
+    pub scale: [f64; 3],
+}
+
+fn vec_to_array(v: &Vec3f) -> [f64; 3] {
+    return [v.x as f64, v.y as f64, v.z as f64];
+}
+
+impl Quantization {
+    /// Quantization spanning vertices bounding box with all steps of dtype
+    pub fn from_vertices(vertices: &Vec<Vec3f>, dtype: QuantizationDtype) -> Quantization {
+        let mut min = [f64::MAX; 3];
+        let mut max = [f64::MIN; 3];
+        for v in vertices.iter() {
+            let pos = vec_to_array(v);
+            for i in 0..3 {
+                min[i] = min[i].min(pos[i]);
+                max[i] = max[i].max(pos[i]);
+            }
+        }
+        if vertices.is_empty() {
+            min = [0f64; 3];
+            max = [0f64; 3];
+        }
+        let scale = [0, 1, 2].map(|i| (max[i] - min[i]) / dtype.max_value());
+        return Quantization {
+            dtype,
+            offset: min,
+            scale,
+        };
+    }
+
+    fn quantize_value(&self, value: f64, axis: usize) -> u64 {
+        if self.scale[axis] <= 0f64 {
+            return 0;
+        }
+        let q = ((value - self.offset[axis]) / self.scale[axis]).round();
+        return q.clamp(0f64, self.dtype.max_value()) as u64;
+    }
+
+    fn dequantize_value(&self, q: u64, axis: usize) -> f32 {
+        return (self.offset[axis] + q as f64 * self.scale[axis]) as f32;
+    }
+
+    /// Quantized coordinates as little-endian bytes, (x, y, z) for each vertex
+    pub fn quantize(&self, vertices: &Vec<Vec3f>) -> Vec<u8> {
+        let mut bytes: Vec<u8> = Vec::with_capacity(3 * self.dtype.n_bytes() * vertices.len());
+        for v in vertices.iter() {
+            for (axis, value) in vec_to_array(v).into_iter().enumerate() {
+                let q = self.quantize_value(value, axis);
+                match self.dtype {
+                    QuantizationDtype::U16 => bytes.extend((q as u16).to_le_bytes()),
+                    QuantizationDtype::U32 => bytes.extend((q as u32).to_le_bytes()),
+                }
+            }
+        }
+        return bytes;
+    }
+
+    pub fn dequantize(&self, bytes: &[u8]) -> Result<Vec<Vec3f>, Box<dyn Error>> {
+        let n_bytes = self.dtype.n_bytes();
+        if !bytes.len().is_multiple_of(3 * n_bytes) {
+            return Err(format!(
+                "Invalid number of quantized vertices bytes {}, expected a multiple of {}",
+                bytes.len(),
+                3 * n_bytes
+            )
+            .into());
+        }
+        let values: Vec<u64> = bytes
+            .chunks_exact(n_bytes)
+            .map(|b| match self.dtype {
+                QuantizationDtype::U16 => u16::from_le_bytes([b[0], b[1]]) as u64,
+                QuantizationDtype::U32 => u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as u64,
+            })
+            .collect();
+        return Ok(values
+            .chunks_exact(3)
+            .map(|q| Vec3f {
+                x: self.dequantize_value(q[0], 0),
+                y: self.dequantize_value(q[1], 1),
+                z: self.dequantize_value(q[2], 2),
+            })
+            .collect());
+    }
+}
+
+/// Size of vertices array before and after quantization and the largest coordinate error
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct QuantizationReport {
+    pub dtype: QuantizationDtype,
+    pub raw_size: usize,
+    pub quantized_size: usize,
+    pub max_error: f32,
+}
+
+impl fmt::Display for QuantizationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(
+            f,
+            "Quantized vertices to {} from {} to {} bytes, maximum error {:.4e}",
+            self.dtype.name(),
+            self.raw_size,
+            self.quantized_size,
+            self.max_error
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lagrangian::test_utils::get_joined_stl;
+
+    #[test]
+    fn check_quantization_error_terrain() {
+        let (vertices, _) = get_joined_stl("terrain");
+
+        for dtype in [QuantizationDtype::U16, QuantizationDtype::U32] {
+            let quantization = Quantization::from_vertices(&vertices, dtype);
+            let bytes = quantization.quantize(&vertices);
+            assert_eq!(bytes.len(), 3 * dtype.n_bytes() * vertices.len());
+            let dequantized = quantization.dequantize(&bytes).unwrap();
+            assert_eq!(dequantized.len(), vertices.len());
+            for (v0, v1) in vertices.iter().zip(dequantized.iter()) {
+                for axis in 0..3 {
+                    // Error is up to half step, plus f32 rounding
+                    let step = quantization.scale[axis] as f32;
+                    let error = (vec_to_array(v0)[axis] - vec_to_array(v1)[axis]).abs() as f32;
+                    assert!(error <= 0.5 * step + 1e-6 * v0.norm().max(1f32));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn check_quantization_flat_axis() {
+        // Bounding box corners are recovered exactly, flat axes have null scale
+        let vertices: Vec<Vec3f> = [1f32, 3f32, 1f32]
+            .into_iter()
+            .map(|x| Vec3f {
+                x,
+                y: 2f32,
+                z: 5f32,
+            })
+            .collect();
+        let quantization = Quantization::from_vertices(&vertices, QuantizationDtype::U16);
+        assert_eq!(quantization.offset, [1f64, 2f64, 5f64]);
+        assert_eq!(quantization.scale[1], 0f64);
+        let dequantized = quantization
+            .dequantize(&quantization.quantize(&vertices))
+            .unwrap();
+        assert_eq!(dequantized, vertices);
+        assert!(quantization.dequantize(&[0u8; 5]).is_err());
+        assert!(QuantizationDtype::from_name("u8").is_err());
+    }
+}
@@ -0,0 +1,66 @@
+use crate::lagrangian::binary::{is_lnasb, read_lnasb_bytes};
+use crate::lagrangian::format::LNAS;
+use crate::lagrangian::metadata::MetadataLNAS;
+use std::error::Error;
+use std::{fs, path};
+
+/// Read LNAS file, either in YAML or binary layout
+pub fn read_lnas(filename: &path::Path) -> Result<LNAS, Box<dyn Error>> {
+    let content = fs::read(filename)?;
+    if is_lnasb(&content) {
+        return read_lnasb_bytes(&content);
+    }
+    let lnas_obj: LNAS = serde_yaml::from_slice(&content)?;
+    return Ok(lnas_obj);
+}
+
+/// Read metadata of LNAS file, if it has one
+pub fn read_lnas_metadata(filename: &path::Path) -> Result<Option<MetadataLNAS>, Box<dyn Error>> {
+    return Ok(read_lnas(filename)?.metadata);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lagrangian::attributes::{get_surfaces_attributes, SurfacesAttributesCfg};
+    use crate::lagrangian::format::get_lnas_obj_save;
+    use crate::lagrangian::save::save_lnas;
+    use crate::lagrangian::test_utils::get_joined_stls;
+    use std::collections::HashMap;
+
+    #[test]
+    fn check_read_lnas_formats() {
+        let (vertices, triangles, surfaces) = get_joined_stls(&["cube"]);
+        let mut lnas_obj = get_lnas_obj_save(&vertices, &triangles, &surfaces);
+        let mut files: HashMap<String, path::PathBuf> = HashMap::new();
+        files.insert(
+            "cube".to_string(),
+            path::Path::new("examples/stl/cube.stl").to_owned(),
+        );
+        let metadata = MetadataLNAS::new(&files, vec!["stl2lnas".to_string()], None).unwrap();
+        lnas_obj.set_metadata(metadata).unwrap();
+        let attributes_cfg = SurfacesAttributesCfg::from_file(path::Path::new(
+            "examples/cfg/surfaces_attributes.yaml",
+        ))
+        .unwrap();
+        lnas_obj.set_surfaces_attributes(get_surfaces_attributes(&attributes_cfg, &surfaces));
+
+        for filename in ["output/reader/cube.lnas", "output/reader/cube.lnasb"] {
+            let filename = path::Path::new(filename);
+            save_lnas(filename, &lnas_obj).unwrap();
+            let read_obj = read_lnas(filename).unwrap();
+            assert!(read_obj == lnas_obj);
+            assert_eq!(read_obj.get_vertices().unwrap(), vertices);
+
+            let metadata = read_lnas_metadata(filename).unwrap().unwrap();
+            assert_eq!(metadata.sources[0].name, "cube");
+            assert_eq!(metadata.n_vertices, vertices.len());
+            assert_eq!(metadata.n_triangles, 12);
+            assert_eq!(metadata.n_surfaces, 1);
+
+            let attributes = read_obj.get_surface_attributes("cube").unwrap();
+            assert_eq!(attributes.get("boundary_condition").unwrap(), "wall");
+            assert!(read_obj.get_surface_attributes("plane").is_none());
+        }
+    }
+}
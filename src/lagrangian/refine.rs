@@ -0,0 +1,475 @@
+use crate::lagrangian::features::FeatureEdges;
+use crate::lagrangian::mesh::{edge_key, get_edges_triangles, triangle_directed_edges, Edge};
+use crate::utils::{Vec3f, Vec3u};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::{fmt, fs, path};
+
+/// Maximum number of bisection passes when refining by edge length
+const MAX_LENGTH_PASSES: usize = 64;
+
+/// Refinement of a surface. If both are given, both are applied.
+#[derive(Clone, Copy, Debug, PartialEq, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SurfaceRefinement {
+    /// Triangles edges are bisected until they are shorter than it
+    pub max_edge_length: Option<f32>,
+    /// Number of times each triangle is divided in 4
+    pub refinement_level: Option<u32>,
+}
+
+/// Refinement configuration, keyed by surface name
+#[derive(Clone, Debug, PartialEq, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RefinementCfg {
+    pub surfaces: HashMap<String, SurfaceRefinement>,
+}
+
+impl RefinementCfg {
+    pub fn from_file(filename: &path::Path) -> Result<RefinementCfg, Box<dyn Error>> {
+        let file = fs::File::open(filename)?;
+        let cfg: RefinementCfg = serde_yaml::from_reader(file)?;
+        return Ok(cfg);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RefinementReport {
+    pub n_triangles_before: usize,
+    pub n_triangles_after: usize,
+    pub n_vertices_added: usize,
+    /// Triangles with level raised so neighbours levels differ by at most one
+    pub n_graded_triangles: usize,
+    /// Triangles still longer than their maximum edge length after all bisection passes
+    pub n_above_max_length: usize,
+}
+
+impl fmt::Display for RefinementReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Refined from {} to {} triangles, adding {} vertices",
+            self.n_triangles_before, self.n_triangles_after, self.n_vertices_added
+        )?;
+        if self.n_graded_triangles > 0 {
+            write!(
+                f,
+                "\n  {} neighbour triangles refined for grading",
+                self.n_graded_triangles
+            )?;
+        }
+        if self.n_above_max_length > 0 {
+            write!(
+                f,
+                "\n  Warning: {} triangles still above maximum edge length after {} passes",
+                self.n_above_max_length, MAX_LENGTH_PASSES
+            )?;
+        }
+        return Ok(());
+    }
+}
+
+/// Raise levels so triangles sharing an edge differ by at most one level.
+///
+/// Returns the number of triangles with raised level.
+fn grade_levels(triangles: &Vec<Vec3u>, levels: &mut [u32]) -> usize {
+    let original_levels = levels.to_vec();
+    let edges_triangles = get_edges_triangles(triangles);
+    // Propagate from the finest triangles, so each is raised once
+    let mut order: Vec<usize> = (0..triangles.len()).filter(|t| levels[*t] > 1).collect();
+    order.sort_by_key(|t| std::cmp::Reverse(levels[*t]));
+    let mut stack: Vec<usize> = order.into_iter().rev().collect();
+    while let Some(t_idx) = stack.pop() {
+        let min_level = levels[t_idx].saturating_sub(1);
+        for (v0, v1) in triangle_directed_edges(&triangles[t_idx]) {
+            for n_idx in edges_triangles.get(&edge_key(v0, v1)).unwrap().iter() {
+                if levels[*n_idx] < min_level {
+                    levels[*n_idx] = min_level;
+                    stack.push(*n_idx);
+                }
+            }
+        }
+    }
+    return levels
+        .iter()
+        .zip(original_levels.iter())
+        .filter(|(l, o)| l != o)
+        .count();
+}
+
+/// Mesh being refined, keeping track of the original triangle of each one
+struct Refiner<'a> {
+    vertices: &'a mut Vec<Vec3f>,
+    triangles: Vec<Vec3u>,
+    /// Original triangle index of each triangle
+    origins: Vec<usize>,
+    /// Refinement levels still to apply in each triangle
+    levels: Vec<u32>,
+    midpoints: HashMap<Edge, u32>,
+}
+
+impl Refiner<'_> {
+    fn get_midpoint(&mut self, v0: u32, v1: u32) -> u32 {
+        let key = edge_key(v0, v1);
+        if let Some(m) = self.midpoints.get(&key) {
+            return *m;
+        }
+        let mut midpoint = self.vertices[v0 as usize] + self.vertices[v1 as usize];
+        midpoint.divide(2f32);
+        let m = self.vertices.len() as u32;
+        self.vertices.push(midpoint);
+        self.midpoints.insert(key, m);
+        return m;
+    }
+
+    /// Split triangles in the marked edges.
+    ///
+    /// Any triangle with a marked edge is split, so the mesh stays conforming, unless
+    /// `only_pending` is set, when triangles without pending levels are kept (leaving hanging
+    /// vertices to close later). Triangles fully marked are divided in 4 and have their pending
+    /// levels decreased.
+    fn split_edges(&mut self, marked: &HashSet<Edge>, only_pending: bool) {
+        let mut new_triangles: Vec<Vec3u> = Vec::with_capacity(self.triangles.len());
+        let mut new_origins: Vec<usize> = Vec::with_capacity(self.triangles.len());
+        let mut new_levels: Vec<u32> = Vec::with_capacity(self.triangles.len());
+
+        let triangles = std::mem::take(&mut self.triangles);
+        for (t_idx, t) in triangles.iter().enumerate() {
+            let edges = triangle_directed_edges(t);
+            let is_marked = edges.map(|(v0, v1)| marked.contains(&edge_key(v0, v1)));
+            let mut n_marked = is_marked.iter().filter(|m| **m).count();
+            let p = [t.x, t.y, t.z];
+            let mut level = self.levels[t_idx];
+            if only_pending && level == 0 {
+                n_marked = 0;
+            }
+
+            let children: Vec<[u32; 3]> = match n_marked {
+                0 => vec![p],
+                1 => {
+                    // Rotate so marked edge is p0->p1
+                    let i = is_marked.iter().position(|m| *m).unwrap();
+                    let (a, b, c) = (p[i], p[(i + 1) % 3], p[(i + 2) % 3]);
+                    let m = self.get_midpoint(a, b);
+                    vec![[a, m, c], [m, b, c]]
+                }
+                2 => {
+                    // Rotate so unmarked edge is p2->p0
+                    let j = is_marked.iter().position(|m| !*m).unwrap();
+                    let (a, b, c) = (p[(j + 1) % 3], p[(j + 2) % 3], p[j]);
+                    let m_ab = self.get_midpoint(a, b);
+                    let m_bc = self.get_midpoint(b, c);
+                    vec![[m_ab, b, m_bc], [a, m_ab, m_bc], [a, m_bc, c]]
+                }
+                _ => {
+                    let (a, b, c) = (p[0], p[1], p[2]);
+                    let m_ab = self.get_midpoint(a, b);
+                    let m_bc = self.get_midpoint(b, c);
+                    let m_ca = self.get_midpoint(c, a);
+                    level = level.saturating_sub(1);
+                    vec![
+                        [a, m_ab, m_ca],
+                        [m_ab, b, m_bc],
+                        [m_ca, m_bc, c],
+                        [m_ab, m_bc, m_ca],
+                    ]
+                }
+            };
+            for [x, y, z] in children.into_iter() {
+                new_triangles.push(Vec3u { x, y, z });
+                new_origins.push(self.origins[t_idx]);
+                new_levels.push(level);
+            }
+        }
+        self.triangles = new_triangles;
+        self.origins = new_origins;
+        self.levels = new_levels;
+    }
+
+    /// Feature edges after refinement, replacing split edges by its halves
+    fn refine_features(&self, features: &FeatureEdges) -> FeatureEdges {
+        let mut edges: HashSet<Edge> = HashSet::new();
+        let mut stack: Vec<Edge> = features.edges.clone();
+        while let Some((v0, v1)) = stack.pop() {
+            match self.midpoints.get(&(v0, v1)) {
+                Some(m) => {
+                    stack.push(edge_key(v0, *m));
+                    stack.push(edge_key(*m, v1));
+                }
+                None => {
+                    edges.insert((v0, v1));
+                }
+            }
+        }
+        return FeatureEdges::new(edges);
+    }
+
+    /// Divide triangles in 4 for each of its levels.
+    ///
+    /// Neighbours without pending levels are only split once, after all levels, to close the
+    /// hanging vertices. With graded levels, this keeps them from becoming fans of slivers.
+    fn refine_levels(&mut self) {
+        loop {
+            let marked: HashSet<Edge> = self
+                .triangles
+                .iter()
+                .zip(self.levels.iter())
+                .filter(|(_, l)| **l > 0)
+                .flat_map(|(t, _)| triangle_directed_edges(t).map(|(v0, v1)| edge_key(v0, v1)))
+                .collect();
+            if marked.is_empty() {
+                break;
+            }
+            self.split_edges(&marked, true);
+        }
+        let hanging: HashSet<Edge> = self
+            .triangles
+            .iter()
+            .flat_map(|t| triangle_directed_edges(t).map(|(v0, v1)| edge_key(v0, v1)))
+            .filter(|e| self.midpoints.contains_key(e))
+            .collect();
+        self.split_edges(&hanging, false);
+    }
+
+    /// Bisect the longest edge of triangles longer than their maximum edge length.
+    ///
+    /// Returns the number of triangles still too long after `max_passes`.
+    fn refine_lengths(&mut self, max_lengths: &Vec<Option<f32>>, max_passes: usize) -> usize {
+        let mut n_passes = 0;
+        loop {
+            let mut marked: HashSet<Edge> = HashSet::new();
+            for (t, origin) in self.triangles.iter().zip(self.origins.iter()) {
+                let max_length = match max_lengths[*origin] {
+                    Some(l) => l,
+                    None => continue,
+                };
+                let longest = triangle_directed_edges(t)
+                    .map(|(v0, v1)| {
+                        let length =
+                            (self.vertices[v0 as usize] - self.vertices[v1 as usize]).norm();
+                        (length, edge_key(v0, v1))
+                    })
+                    .into_iter()
+                    .max_by(|a, b| a.0.total_cmp(&b.0))
+                    .unwrap();
+                if longest.0 > max_length {
+                    marked.insert(longest.1);
+                }
+            }
+            if marked.is_empty() || n_passes == max_passes {
+                return marked.len();
+            }
+            self.split_edges(&marked, false);
+            n_passes += 1;
+        }
+    }
+}
+
+/// Refine triangles of each surface as given in configuration.
+///
+/// When a triangle is in more than one surface, the finest refinement is used.
+/// Levels are graded, raising neighbours so triangles sharing an edge differ by at most one
+/// level, and neighbour triangles of refined ones are also divided, so no hanging vertices
+/// are created.
+/// Vertices are never moved, and split feature edges are replaced by their halves.
+pub fn refine_surfaces(
+    vertices: &mut Vec<Vec3f>,
+    triangles: &mut Vec<Vec3u>,
+    surfaces: &mut HashMap<String, Vec<u32>>,
+    features: Option<&mut FeatureEdges>,
+    cfg: &RefinementCfg,
+) -> RefinementReport {
+    let n_vertices_before = vertices.len();
+    let n_triangles_before = triangles.len();
+
+    let mut levels: Vec<u32> = vec![0; triangles.len()];
+    let mut max_lengths: Vec<Option<f32>> = vec![None; triangles.len()];
+    for (surface_name, refinement) in cfg.surfaces.iter() {
+        let triangles_idxs = match surfaces.get(surface_name) {
+            Some(idxs) => idxs,
+            None => {
+                println!("Surface '{}' to refine not found", surface_name);
+                continue;
+            }
+        };
+        for t_idx in triangles_idxs.iter().map(|t| *t as usize) {
+            if let Some(level) = refinement.refinement_level {
+                levels[t_idx] = levels[t_idx].max(level);
+            }
+            if let Some(length) = refinement.max_edge_length {
+                max_lengths[t_idx] = Some(max_lengths[t_idx].map_or(length, |l| l.min(length)));
+            }
+        }
+    }
+
+    let n_graded_triangles = grade_levels(triangles, &mut levels);
+
+    let mut refiner = Refiner {
+        vertices,
+        triangles: triangles.clone(),
+        origins: (0..triangles.len()).collect(),
+        levels,
+        midpoints: HashMap::new(),
+    };
+    refiner.refine_levels();
+    let n_above_max_length = refiner.refine_lengths(&max_lengths, MAX_LENGTH_PASSES);
+    if let Some(features) = features {
+        *features = refiner.refine_features(features);
+    }
+
+    let mut children: Vec<Vec<u32>> = vec![Vec::new(); n_triangles_before];
+    for (t_idx, origin) in refiner.origins.iter().enumerate() {
+        children[*origin].push(t_idx as u32);
+    }
+    for triangles_idxs in surfaces.values_mut() {
+        *triangles_idxs = triangles_idxs
+            .iter()
+            .flat_map(|t_idx| children[*t_idx as usize].iter().cloned())
+            .collect();
+    }
+    *triangles = refiner.triangles;
+
+    return RefinementReport {
+        n_triangles_before,
+        n_triangles_after: triangles.len(),
+        n_vertices_added: vertices.len() - n_vertices_before,
+        n_graded_triangles,
+        n_above_max_length,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_almost_equal;
+    use crate::lagrangian::features::detect_feature_edges;
+    use crate::lagrangian::mesh::{triangle_area, triangle_normal, triangle_points};
+    use crate::lagrangian::quality::analyze_mesh;
+    use crate::lagrangian::test_utils::get_joined_stls;
+    use crate::stl::triangle::TriangleSTL;
+
+    fn total_area(vertices: &Vec<Vec3f>, triangles: &Vec<Vec3u>) -> f32 {
+        return triangles.iter().map(|t| triangle_area(vertices, t)).sum();
+    }
+
+    fn min_angle(vertices: &Vec<Vec3f>, triangles: &Vec<Vec3u>) -> f32 {
+        return triangles
+            .iter()
+            .map(|t| {
+                let [point0, point1, point2] = triangle_points(vertices, t);
+                TriangleSTL {
+                    point0,
+                    point1,
+                    point2,
+                    normal: triangle_normal(vertices, t),
+                }
+                .min_angle()
+            })
+            .fold(f32::MAX, f32::min);
+    }
+
+    #[test]
+    fn check_refine_level_cube() {
+        let (mut vertices, mut triangles, mut surfaces) = get_joined_stls(&["cube"]);
+        let area = total_area(&vertices, &triangles);
+        let cfg = RefinementCfg {
+            surfaces: HashMap::from([(
+                "cube".to_string(),
+                SurfaceRefinement {
+                    refinement_level: Some(2),
+                    ..Default::default()
+                },
+            )]),
+        };
+        let mut features = detect_feature_edges(&vertices, &triangles, 30f32);
+        let report = refine_surfaces(
+            &mut vertices,
+            &mut triangles,
+            &mut surfaces,
+            Some(&mut features),
+            &cfg,
+        );
+
+        assert_eq!(report.n_triangles_after, 12 * 16);
+        assert_eq!(surfaces.get("cube").unwrap().len(), 12 * 16);
+        assert_almost_equal!(total_area(&vertices, &triangles), area, 1e-3 * area);
+        let quality = analyze_mesh(&vertices, &triangles);
+        assert!(quality.is_watertight());
+        assert!(quality.is_consistently_oriented());
+        // Each cube edge is split in 4
+        assert!(features == detect_feature_edges(&vertices, &triangles, 30f32));
+        assert_eq!(features.edges.len(), 12 * 4);
+        assert_eq!(features.corners.len(), 8);
+    }
+
+    #[test]
+    fn check_refine_length_keeps_conforming() {
+        let (mut vertices, mut triangles, mut surfaces) = get_joined_stls(&["cube", "sphere"]);
+        let n_sphere = surfaces.get("sphere").unwrap().len();
+        let max_length = 5f32;
+        let cfg = RefinementCfg {
+            surfaces: HashMap::from([(
+                "cube".to_string(),
+                SurfaceRefinement {
+                    max_edge_length: Some(max_length),
+                    ..Default::default()
+                },
+            )]),
+        };
+        refine_surfaces(&mut vertices, &mut triangles, &mut surfaces, None, &cfg);
+
+        assert_eq!(surfaces.get("sphere").unwrap().len(), n_sphere);
+        for t_idx in surfaces.get("cube").unwrap().iter() {
+            let t = triangles[*t_idx as usize];
+            for (v0, v1) in triangle_directed_edges(&t) {
+                let length = (vertices[v0 as usize] - vertices[v1 as usize]).norm();
+                assert!(length <= max_length);
+            }
+        }
+        let quality = analyze_mesh(&vertices, &triangles);
+        assert!(quality.is_watertight());
+        assert!(quality.is_consistently_oriented());
+    }
+
+    #[test]
+    fn check_refine_level_graded() {
+        let (mut vertices, mut triangles, mut surfaces) = get_joined_stls(&["cube"]);
+        // Refine a single triangle, so its neighbours have lower levels
+        surfaces.insert("top".to_string(), vec![0]);
+        let cfg = RefinementCfg {
+            surfaces: HashMap::from([(
+                "top".to_string(),
+                SurfaceRefinement {
+                    refinement_level: Some(3),
+                    ..Default::default()
+                },
+            )]),
+        };
+        let angle_before = min_angle(&vertices, &triangles);
+        let report = refine_surfaces(&mut vertices, &mut triangles, &mut surfaces, None, &cfg);
+
+        assert!(report.n_graded_triangles > 0);
+        assert_eq!(surfaces.get("top").unwrap().len(), 64);
+        let quality = analyze_mesh(&vertices, &triangles);
+        assert!(quality.is_watertight());
+        assert!(quality.is_consistently_oriented());
+        // Neighbours are split once, instead of becoming fans of slivers (below 4 degrees)
+        assert!(min_angle(&vertices, &triangles) >= angle_before / 3f32);
+    }
+
+    #[test]
+    fn check_refine_length_passes_limit() {
+        let (mut vertices, triangles, _) = get_joined_stls(&["cube"]);
+        let mut refiner = Refiner {
+            vertices: &mut vertices,
+            triangles: triangles.clone(),
+            origins: (0..triangles.len()).collect(),
+            levels: vec![0; triangles.len()],
+            midpoints: HashMap::new(),
+        };
+        let max_lengths = vec![Some(0.25f32); triangles.len()];
+        assert!(refiner.refine_lengths(&max_lengths, 2) > 0);
+        assert_eq!(refiner.refine_lengths(&max_lengths, MAX_LENGTH_PASSES), 0);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::lagrangian::binary::save_lnasb;
 use crate::lagrangian::format::LNAS;
 use crate::utils::create_folder_for_filename;
 use std::error::Error;
@@ -5,7 +6,15 @@ use std::{fs, path};
 
 use serde_yaml;
 
+/// Extension of binary LNAS files
+pub const LNASB_EXTENSION: &str = "lnasb";
+
+/// Save LNAS, in binary layout when filename extension is `.lnasb` and YAML otherwise
 pub fn save_lnas(filename: &path::Path, lnas_obj: &LNAS) -> Result<(), Box<dyn Error>> {
+    if filename.extension().is_some_and(|e| e == LNASB_EXTENSION) {
+        println!("Saving binary...");
+        return save_lnasb(filename, lnas_obj);
+    }
     create_folder_for_filename(filename)?;
     let file = fs::File::create(filename)?;
     println!("Saving...");
@@ -17,27 +26,12 @@ pub fn save_lnas(filename: &path::Path, lnas_obj: &LNAS) -> Result<(), Box<dyn E
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::lagrangian::format::get_lnas_obj_save;
-    use crate::lagrangian::join::join_information;
-    use crate::lagrangian::triangle::generate_lagrangian_triangles;
-    use crate::lagrangian::vertice::generate_lagrangian_vertices;
-    use crate::stl::surfaces::get_surfaces;
-    use std::{collections::HashMap, path, string::String};
+    use crate::lagrangian::test_utils::get_lnas_obj;
+    use std::path;
 
     #[test]
     fn check_join_info_stl_cube() {
-        let mut files: HashMap<String, path::PathBuf> = HashMap::new();
-        let filename: String = "examples/stl/cube.stl".to_string();
-        files.insert(
-            "cube".to_string(),
-            path::Path::new(filename.as_str()).to_owned(),
-        );
-
-        let (triangles, surfaces) = get_surfaces(&files);
-        let lagr_vertices = generate_lagrangian_vertices(&triangles);
-        let lagr_triangles = generate_lagrangian_triangles(&lagr_vertices, &triangles);
-        let (joined_vertices, joined_triangles) = join_information(&lagr_vertices, &lagr_triangles);
-        let lnas_obj = get_lnas_obj_save(&joined_vertices, &joined_triangles, &surfaces);
+        let lnas_obj = get_lnas_obj(&["cube"]);
 
         let folder_path = path::Path::new("output/");
         let lnas_filename = folder_path.join(format!("{}.lnas", "cube"));
@@ -47,19 +41,7 @@ mod tests {
 
     #[test]
     fn check_join_info_stl_terrain() {
-        // let filename_cfg = String::from("examples/convert_terrain.yaml");
-        let mut files: HashMap<String, path::PathBuf> = HashMap::new();
-        let filename: String = "examples/stl/terrain.stl".to_string();
-        files.insert(
-            "cube".to_string(),
-            path::Path::new(filename.as_str()).to_owned(),
-        );
-
-        let (triangles, surfaces) = get_surfaces(&files);
-        let lagr_vertices = generate_lagrangian_vertices(&triangles);
-        let lagr_triangles = generate_lagrangian_triangles(&lagr_vertices, &triangles);
-        let (joined_vertices, joined_triangles) = join_information(&lagr_vertices, &lagr_triangles);
-        let lnas_obj = get_lnas_obj_save(&joined_vertices, &joined_triangles, &surfaces);
+        let lnas_obj = get_lnas_obj(&["terrain"]);
 
         let folder_path = path::Path::new("output/");
         let lnas_filename = folder_path.join(format!("{}.lnas", "terrain"));
@@ -69,23 +51,7 @@ mod tests {
 
     #[test]
     fn check_join_info_stl_terrain_cube() {
-        let mut files: HashMap<String, path::PathBuf> = HashMap::new();
-        let filename: String = "examples/stl/plane.stl".to_string();
-        files.insert(
-            "plane".to_string(),
-            path::Path::new(filename.as_str()).to_owned(),
-        );
-        let filename: String = "examples/stl/cube.stl".to_string();
-        files.insert(
-            "cube".to_string(),
-            path::Path::new(filename.as_str()).to_owned(),
-        );
-
-        let (triangles, surfaces) = get_surfaces(&files);
-        let lagr_vertices = generate_lagrangian_vertices(&triangles);
-        let lagr_triangles = generate_lagrangian_triangles(&lagr_vertices, &triangles);
-        let (joined_vertices, joined_triangles) = join_information(&lagr_vertices, &lagr_triangles);
-        let lnas_obj = get_lnas_obj_save(&joined_vertices, &joined_triangles, &surfaces);
+        let lnas_obj = get_lnas_obj(&["plane", "cube"]);
 
         let folder_path = path::Path::new("output/");
         let lnas_filename = folder_path.join(format!("{}.lnas", "plane_cube"));
@@ -0,0 +1,205 @@
+use crate::lagrangian::features::detect_feature_edges;
+use crate::lagrangian::mesh::{edge_key, get_edges_triangles, triangle_directed_edges, Edge};
+use crate::utils::{Vec3f, Vec3u};
+use clap::ValueEnum;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Criteria to split surfaces in sub-surfaces
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum SplitMode {
+    /// Connected components of each surface
+    Components,
+    /// Regions of each surface bounded by feature edges (also splits components)
+    Features,
+}
+
+/// Surface split in sub-surfaces
+#[derive(Clone, Debug, PartialEq)]
+pub struct SurfaceSplit {
+    pub surface_name: String,
+    /// Sub-surfaces names and number of triangles
+    pub sub_surfaces: Vec<(String, usize)>,
+}
+
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct SplitReport {
+    pub splits: Vec<SurfaceSplit>,
+}
+
+impl fmt::Display for SplitReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Split {} surfaces", self.splits.len())?;
+        for split in self.splits.iter() {
+            write!(
+                f,
+                "\n  '{}' in {} surfaces:",
+                split.surface_name,
+                split.sub_surfaces.len()
+            )?;
+            for (name, n_triangles) in split.sub_surfaces.iter() {
+                write!(f, " {} ({} triangles)", name, n_triangles)?;
+            }
+        }
+        return Ok(());
+    }
+}
+
+/// Regions of surface triangles connected by manifold edges that may be crossed.
+///
+/// Regions are sorted and ordered by their first triangle.
+fn get_surface_regions(
+    triangles: &Vec<Vec3u>,
+    surface_triangles: &Vec<u32>,
+    edges_triangles: &HashMap<Edge, Vec<usize>>,
+    can_cross: &impl Fn(&Edge) -> bool,
+) -> Vec<Vec<u32>> {
+    let mut region_of: HashMap<usize, Option<usize>> = surface_triangles
+        .iter()
+        .map(|t_idx| (*t_idx as usize, None))
+        .collect();
+    let mut sorted_triangles = surface_triangles.clone();
+    sorted_triangles.sort();
+
+    let mut regions: Vec<Vec<u32>> = Vec::new();
+    for start in sorted_triangles.into_iter() {
+        let start = start as usize;
+        if region_of.get(&start).unwrap().is_some() {
+            continue;
+        }
+        let region_idx = regions.len();
+        let mut region: Vec<u32> = Vec::new();
+        let mut stack: Vec<usize> = vec![start];
+        region_of.insert(start, Some(region_idx));
+        while let Some(t_idx) = stack.pop() {
+            region.push(t_idx as u32);
+            for (v0, v1) in triangle_directed_edges(&triangles[t_idx]) {
+                let edge = edge_key(v0, v1);
+                let neighbours = edges_triangles.get(&edge).unwrap();
+                if neighbours.len() != 2 || !can_cross(&edge) {
+                    continue;
+                }
+                for n_idx in neighbours.iter() {
+                    // Only triangles from the same surface not yet visited
+                    if let Some(None) = region_of.get(n_idx) {
+                        region_of.insert(*n_idx, Some(region_idx));
+                        stack.push(*n_idx);
+                    }
+                }
+            }
+        }
+        region.sort();
+        regions.push(region);
+    }
+    return regions;
+}
+
+/// Split each surface in sub-surfaces, named `<surface>_<n>`.
+///
+/// Surfaces with a single region keep their name.
+pub fn split_surfaces(
+    vertices: &Vec<Vec3f>,
+    triangles: &Vec<Vec3u>,
+    surfaces: &HashMap<String, Vec<u32>>,
+    mode: SplitMode,
+    feature_angle: f32,
+) -> (HashMap<String, Vec<u32>>, SplitReport) {
+    let edges_triangles = get_edges_triangles(triangles);
+    let features = match mode {
+        SplitMode::Components => None,
+        SplitMode::Features => Some(detect_feature_edges(vertices, triangles, feature_angle)),
+    };
+    let can_cross = |edge: &Edge| match &features {
+        Some(features) => !features.contains(edge),
+        None => true,
+    };
+
+    let mut surface_names: Vec<&String> = surfaces.keys().collect();
+    surface_names.sort();
+
+    let mut new_surfaces: HashMap<String, Vec<u32>> = HashMap::new();
+    let mut report = SplitReport::default();
+    for surface_name in surface_names.into_iter() {
+        let surface_triangles = surfaces.get(surface_name).unwrap();
+        let regions =
+            get_surface_regions(triangles, surface_triangles, &edges_triangles, &can_cross);
+        if regions.len() <= 1 {
+            new_surfaces.insert(surface_name.to_owned(), surface_triangles.clone());
+            continue;
+        }
+        let mut split = SurfaceSplit {
+            surface_name: surface_name.to_owned(),
+            sub_surfaces: Vec::new(),
+        };
+        for (region_idx, region) in regions.into_iter().enumerate() {
+            let name = format!("{}_{}", surface_name, region_idx);
+            if surfaces.contains_key(&name) {
+                panic!(
+                    "Unable to split surface '{}', surface '{}' already exists",
+                    surface_name, name
+                );
+            }
+            split.sub_surfaces.push((name.clone(), region.len()));
+            new_surfaces.insert(name, region);
+        }
+        report.splits.push(split);
+    }
+    return (new_surfaces, report);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lagrangian::test_utils::get_joined_stls;
+
+    #[test]
+    fn check_split_cube_features() {
+        let (vertices, triangles, surfaces) = get_joined_stls(&["cube"]);
+        let (split, report) =
+            split_surfaces(&vertices, &triangles, &surfaces, SplitMode::Features, 30f32);
+        assert_eq!(split.len(), 6);
+        assert_eq!(report.splits.len(), 1);
+        for i in 0..6 {
+            assert_eq!(split.get(&format!("cube_{}", i)).unwrap().len(), 2);
+        }
+
+        let (split, report) = split_surfaces(
+            &vertices,
+            &triangles,
+            &surfaces,
+            SplitMode::Components,
+            30f32,
+        );
+        assert_eq!(split, surfaces);
+        assert_eq!(report.splits.len(), 0);
+    }
+
+    #[test]
+    fn check_split_cylinder_keeps_name() {
+        // Cylinder is an open tube, with boundary only at its ends
+        let (vertices, triangles, surfaces) = get_joined_stls(&["cylinder"]);
+        let (split, report) =
+            split_surfaces(&vertices, &triangles, &surfaces, SplitMode::Features, 60f32);
+        assert_eq!(split, surfaces);
+        assert_eq!(report.splits.len(), 0);
+    }
+
+    #[test]
+    fn check_split_components() {
+        let (vertices, triangles, _) = get_joined_stls(&["cube", "sphere"]);
+        let mut surfaces: HashMap<String, Vec<u32>> = HashMap::new();
+        surfaces.insert("body".to_string(), (0..triangles.len() as u32).collect());
+        let (split, report) = split_surfaces(
+            &vertices,
+            &triangles,
+            &surfaces,
+            SplitMode::Components,
+            30f32,
+        );
+        assert_eq!(split.len(), 2);
+        assert_eq!(report.splits[0].sub_surfaces.len(), 2);
+        let n_triangles: usize = split.values().map(|t| t.len()).sum();
+        assert_eq!(n_triangles, triangles.len());
+        assert!(split.values().any(|t| t.len() == 12));
+    }
+}
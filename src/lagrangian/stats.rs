@@ -0,0 +1,321 @@
+use crate::lagrangian::mesh::{
+    edge_key, get_connected_components, get_edges_triangles, triangle_area, triangle_centroid,
+    triangle_cross, triangle_directed_edges,
+};
+use crate::lagrangian::orientation::get_signed_volume;
+use crate::utils::{create_folder_for_filename, Vec3f, Vec3u};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::{fmt, fs, path};
+
+/// Bounding box in original coordinates (origin shift added back)
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct BoundingBox {
+    pub min: [f64; 3],
+    pub max: [f64; 3],
+}
+
+impl BoundingBox {
+    pub fn size(&self) -> [f64; 3] {
+        return [
+            self.max[0] - self.min[0],
+            self.max[1] - self.min[1],
+            self.max[2] - self.min[2],
+        ];
+    }
+}
+
+/// Position in f64, with origin shift added back
+fn shifted_position(p: Vec3f, origin_shift: &[f64; 3]) -> [f64; 3] {
+    return [
+        p.x as f64 + origin_shift[0],
+        p.y as f64 + origin_shift[1],
+        p.z as f64 + origin_shift[2],
+    ];
+}
+
+fn fmt_position(p: &[f64; 3]) -> String {
+    return format!("({:.2}, {:.2}, {:.2})", p[0], p[1], p[2]);
+}
+
+/// Geometric statistics of a set of triangles
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct GeometryStats {
+    pub n_triangles: usize,
+    pub area: f32,
+    /// Area projected along x, y and z, without considering occlusion.
+    ///
+    /// It's the largest between the areas of triangles facing each side of the axis
+    pub projected_area: Vec3f,
+    pub bounding_box: BoundingBox,
+    /// Area weighted centroid of triangles, in original coordinates
+    pub centroid: [f64; 3],
+    /// Number of connected components without boundary
+    pub n_closed_components: usize,
+    /// Volume enclosed by closed components
+    pub volume: f32,
+}
+
+impl fmt::Display for GeometryStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "    Triangles: {}", self.n_triangles)?;
+        writeln!(f, "    Area: {:.4e}", self.area)?;
+        writeln!(
+            f,
+            "    Projected area (x, y, z): ({:.4e}, {:.4e}, {:.4e})",
+            self.projected_area.x, self.projected_area.y, self.projected_area.z
+        )?;
+        writeln!(
+            f,
+            "    Bounding box: {} to {} (size {})",
+            fmt_position(&self.bounding_box.min),
+            fmt_position(&self.bounding_box.max),
+            fmt_position(&self.bounding_box.size())
+        )?;
+        writeln!(f, "    Centroid: {}", fmt_position(&self.centroid))?;
+        return write!(
+            f,
+            "    Volume: {:.4e} ({} closed components)",
+            self.volume, self.n_closed_components
+        );
+    }
+}
+
+/// Statistics of whole geometry and of each surface
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct StatsReport {
+    pub total: GeometryStats,
+    pub surfaces: BTreeMap<String, GeometryStats>,
+}
+
+impl fmt::Display for StatsReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Geometry statistics")?;
+        write!(f, "  Total:\n{}", self.total)?;
+        for (surface_name, stats) in self.surfaces.iter() {
+            write!(f, "\n  Surface '{}':\n{}", surface_name, stats)?;
+        }
+        return Ok(());
+    }
+}
+
+fn zero_vec() -> Vec3f {
+    return Vec3f {
+        x: 0f32,
+        y: 0f32,
+        z: 0f32,
+    };
+}
+
+fn get_bounding_box(
+    vertices: &Vec<Vec3f>,
+    triangles: &Vec<Vec3u>,
+    origin_shift: &[f64; 3],
+) -> BoundingBox {
+    if triangles.is_empty() {
+        return BoundingBox {
+            min: *origin_shift,
+            max: *origin_shift,
+        };
+    }
+    let mut min = vertices[triangles[0].x as usize];
+    let mut max = min;
+    for t in triangles.iter() {
+        for v in [t.x, t.y, t.z] {
+            let p = vertices[v as usize];
+            min = Vec3f {
+                x: min.x.min(p.x),
+                y: min.y.min(p.y),
+                z: min.z.min(p.z),
+            };
+            max = Vec3f {
+                x: max.x.max(p.x),
+                y: max.y.max(p.y),
+                z: max.z.max(p.z),
+            };
+        }
+    }
+    return BoundingBox {
+        min: shifted_position(min, origin_shift),
+        max: shifted_position(max, origin_shift),
+    };
+}
+
+/// Volume enclosed by components without boundary, returning also their number
+fn get_closed_volume(vertices: &Vec<Vec3f>, triangles: &Vec<Vec3u>) -> (f32, usize) {
+    let edges_triangles = get_edges_triangles(triangles);
+    let mut volume = 0f32;
+    let mut n_closed_components = 0;
+    for component in get_connected_components(triangles, &edges_triangles).iter() {
+        let is_closed = component.iter().all(|t_idx| {
+            triangle_directed_edges(&triangles[*t_idx])
+                .iter()
+                .all(|(v0, v1)| edges_triangles.get(&edge_key(*v0, *v1)).unwrap().len() == 2)
+        });
+        if !is_closed {
+            continue;
+        }
+        n_closed_components += 1;
+        let component_triangles: Vec<Vec3u> =
+            component.iter().map(|t_idx| triangles[*t_idx]).collect();
+        // Absolute value, so it doesn't depend on orientation
+        volume += get_signed_volume(vertices, &component_triangles).abs();
+    }
+    return (volume, n_closed_components);
+}
+
+pub fn get_geometry_stats(
+    vertices: &Vec<Vec3f>,
+    triangles: &Vec<Vec3u>,
+    origin_shift: &[f64; 3],
+) -> GeometryStats {
+    let mut area = 0f32;
+    let mut weighted_centroid = zero_vec();
+    let mut positive_projection = zero_vec();
+    let mut negative_projection = zero_vec();
+    for t in triangles.iter() {
+        let t_area = triangle_area(vertices, t);
+        area += t_area;
+        let mut centroid = triangle_centroid(vertices, t);
+        centroid.multiply(t_area);
+        weighted_centroid += centroid;
+
+        // Components of cross product are two times the projected areas
+        let mut cross = triangle_cross(vertices, t);
+        cross.divide(2f32);
+        positive_projection += Vec3f {
+            x: cross.x.max(0f32),
+            y: cross.y.max(0f32),
+            z: cross.z.max(0f32),
+        };
+        negative_projection += Vec3f {
+            x: (-cross.x).max(0f32),
+            y: (-cross.y).max(0f32),
+            z: (-cross.z).max(0f32),
+        };
+    }
+    if area > 0f32 {
+        weighted_centroid.divide(area);
+    }
+    let (volume, n_closed_components) = get_closed_volume(vertices, triangles);
+
+    return GeometryStats {
+        n_triangles: triangles.len(),
+        area,
+        projected_area: Vec3f {
+            x: positive_projection.x.max(negative_projection.x),
+            y: positive_projection.y.max(negative_projection.y),
+            z: positive_projection.z.max(negative_projection.z),
+        },
+        bounding_box: get_bounding_box(vertices, triangles, origin_shift),
+        centroid: shifted_position(weighted_centroid, origin_shift),
+        n_closed_components,
+        volume,
+    };
+}
+
+/// Statistics of whole geometry and of each surface
+///
+/// Positions are reported in original coordinates, as in LNAS metadata
+pub fn get_stats(
+    vertices: &Vec<Vec3f>,
+    triangles: &Vec<Vec3u>,
+    surfaces: &HashMap<String, Vec<u32>>,
+    origin_shift: &[f64; 3],
+) -> StatsReport {
+    let mut surfaces_stats: BTreeMap<String, GeometryStats> = BTreeMap::new();
+    for (surface_name, triangles_idxs) in surfaces.iter() {
+        let surface_triangles: Vec<Vec3u> = triangles_idxs
+            .iter()
+            .map(|t_idx| triangles[*t_idx as usize])
+            .collect();
+        surfaces_stats.insert(
+            surface_name.to_owned(),
+            get_geometry_stats(vertices, &surface_triangles, origin_shift),
+        );
+    }
+    return StatsReport {
+        total: get_geometry_stats(vertices, triangles, origin_shift),
+        surfaces: surfaces_stats,
+    };
+}
+
+pub fn save_stats(filename: &path::Path, report: &StatsReport) -> Result<(), Box<dyn Error>> {
+    create_folder_for_filename(filename)?;
+    let file = fs::File::create(filename)?;
+    serde_yaml::to_writer(file, report)?;
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_almost_equal;
+    use crate::lagrangian::metadata::get_bounding_box_metadata;
+    use crate::lagrangian::test_utils::get_joined_stls;
+    use crate::stl::origin::NO_SHIFT;
+
+    #[test]
+    fn check_stats_cube() {
+        let (vertices, triangles, surfaces) = get_joined_stls(&["cube"]);
+        let report = get_stats(&vertices, &triangles, &surfaces, &NO_SHIFT);
+        let stats = report.surfaces.get("cube").unwrap();
+        assert_eq!(stats, &report.total);
+
+        let size = stats.bounding_box.size();
+        assert_almost_equal!(size[1], size[0], 1e-5);
+        let side = size[0] as f32;
+        assert_almost_equal!(stats.area, 6f32 * side * side, 1e-4 * stats.area);
+        assert_almost_equal!(stats.projected_area.x, side * side, 1e-4 * stats.area);
+        assert_almost_equal!(stats.projected_area.z, side * side, 1e-4 * stats.area);
+        assert_eq!(stats.n_closed_components, 1);
+        assert_almost_equal!(stats.volume, side * side * side, 1e-4 * stats.volume);
+
+        for i in 0..3 {
+            let center = (stats.bounding_box.min[i] + stats.bounding_box.max[i]) / 2f64;
+            assert_almost_equal!(stats.centroid[i], center, 1e-5);
+        }
+    }
+
+    #[test]
+    fn check_stats_origin_shift() {
+        let (vertices, triangles, surfaces) = get_joined_stls(&["cube"]);
+        let shift = [5e5f64, 7e6f64, -10f64];
+        let report = get_stats(&vertices, &triangles, &surfaces, &shift);
+        let unshifted = get_stats(&vertices, &triangles, &surfaces, &NO_SHIFT);
+
+        // Same bounding box as saved in metadata
+        let metadata_box = get_bounding_box_metadata(&vertices, Some(shift));
+        assert_eq!(report.total.bounding_box.min, metadata_box.min);
+        assert_eq!(report.total.bounding_box.max, metadata_box.max);
+        for i in 0..3 {
+            assert_almost_equal!(
+                report.total.centroid[i],
+                unshifted.total.centroid[i] + shift[i],
+                1e-6
+            );
+        }
+        assert_eq!(report.total.area, unshifted.total.area);
+    }
+
+    #[test]
+    fn check_stats_plane_cube() {
+        let (vertices, triangles, surfaces) = get_joined_stls(&["plane", "cube"]);
+        let report = get_stats(&vertices, &triangles, &surfaces, &NO_SHIFT);
+        let plane = report.surfaces.get("plane").unwrap();
+        let cube = report.surfaces.get("cube").unwrap();
+        assert_eq!(plane.n_closed_components, 0);
+        assert_eq!(plane.volume, 0f32);
+        assert_eq!(
+            report.total.n_triangles,
+            plane.n_triangles + cube.n_triangles
+        );
+        assert_almost_equal!(
+            report.total.area,
+            plane.area + cube.area,
+            1e-4 * report.total.area
+        );
+        assert_almost_equal!(report.total.volume, cube.volume, 1e-4 * cube.volume);
+    }
+}
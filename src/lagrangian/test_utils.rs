@@ -0,0 +1,45 @@
+use crate::lagrangian::format::{get_lnas_obj_save, LNAS};
+use crate::lagrangian::join::join_information;
+use crate::lagrangian::triangle::{generate_lagrangian_triangles, LagrangianTriangle};
+use crate::lagrangian::vertice::{generate_lagrangian_vertices, LagrangianVertice};
+use crate::stl::surfaces::get_surfaces;
+use crate::utils::{Vec3f, Vec3u};
+use std::{collections::HashMap, path};
+
+/// Lagrangian vertices, triangles and surfaces of example STLs (`examples/stl/<name>.stl`)
+pub fn get_lagrangian_stls(
+    names: &[&str],
+) -> (
+    HashMap<LagrangianVertice, usize>,
+    Vec<LagrangianTriangle>,
+    HashMap<String, Vec<u32>>,
+) {
+    let mut files: HashMap<String, path::PathBuf> = HashMap::new();
+    for name in names.iter() {
+        let filename = format!("examples/stl/{}.stl", name);
+        files.insert(name.to_string(), path::Path::new(&filename).to_owned());
+    }
+    let (triangles, surfaces) = get_surfaces(&files);
+    let lagr_vertices = generate_lagrangian_vertices(&triangles);
+    let lagr_triangles = generate_lagrangian_triangles(&lagr_vertices, &triangles);
+    return (lagr_vertices, lagr_triangles, surfaces);
+}
+
+/// Joined vertices, triangles and surfaces of example STLs
+pub fn get_joined_stls(names: &[&str]) -> (Vec<Vec3f>, Vec<Vec3u>, HashMap<String, Vec<u32>>) {
+    let (lagr_vertices, lagr_triangles, surfaces) = get_lagrangian_stls(names);
+    let (vertices, triangles) = join_information(&lagr_vertices, &lagr_triangles);
+    return (vertices, triangles, surfaces);
+}
+
+/// Joined vertices and triangles of a single example STL
+pub fn get_joined_stl(name: &str) -> (Vec<Vec3f>, Vec<Vec3u>) {
+    let (vertices, triangles, _) = get_joined_stls(&[name]);
+    return (vertices, triangles);
+}
+
+/// LNAS of example STLs, without optional fields
+pub fn get_lnas_obj(names: &[&str]) -> LNAS {
+    let (vertices, triangles, surfaces) = get_joined_stls(names);
+    return get_lnas_obj_save(&vertices, &triangles, &surfaces);
+}
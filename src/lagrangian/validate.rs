@@ -0,0 +1,479 @@
+use crate::lagrangian::binary::{is_lnasb, read_lnasb_bytes};
+use crate::lagrangian::format::{
+    bytes_to_f32, bytes_to_u32, bytes_to_vec3f, decode_bytes, ArrayLNAS, LNAS,
+};
+use crate::lagrangian::migrate::parse_version;
+use crate::utils::Vec3f;
+use serde_yaml::Value;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::{fmt, fs, path};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProblemKind {
+    /// Version is not "v<major>.<minor>.<patch>"
+    InvalidVersion,
+    /// Array can't be decoded (compression, encoding or bytes multiple)
+    InvalidArray,
+    /// Array length doesn't match the number of vertices or triangles
+    LengthMismatch,
+    /// Index beyond the referenced array
+    IndexOutOfBounds,
+    /// Triangle more than once in surfaces
+    DuplicatedTriangle,
+    /// Vertex with NaN or infinite coordinate
+    NonFiniteVertex,
+}
+
+impl fmt::Display for ProblemKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ProblemKind::InvalidVersion => "invalid version",
+            ProblemKind::InvalidArray => "invalid array",
+            ProblemKind::LengthMismatch => "length mismatch",
+            ProblemKind::IndexOutOfBounds => "index out of bounds",
+            ProblemKind::DuplicatedTriangle => "duplicated triangle",
+            ProblemKind::NonFiniteVertex => "non finite vertex",
+        };
+        return write!(f, "{}", name);
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidationProblem {
+    pub kind: ProblemKind,
+    /// Field with the problem, such as "geometry.triangles" or "surfaces.cube"
+    pub field: String,
+    pub message: String,
+}
+
+impl fmt::Display for ValidationProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "[{}] {}: {}", self.kind, self.field, self.message);
+    }
+}
+
+/// Problems found in LNAS, listing up to a maximum number of each kind per field
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct ValidationReport {
+    pub problems: Vec<ValidationProblem>,
+}
+
+/// Problems of the same kind and field after this are only counted
+const MAX_PROBLEMS_PER_FIELD: usize = 10;
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        return self.problems.is_empty();
+    }
+
+    fn add(&mut self, kind: ProblemKind, field: &str, message: String) {
+        self.problems.push(ValidationProblem {
+            kind,
+            field: field.to_string(),
+            message,
+        });
+    }
+
+    /// Add problems of a field, summarizing the ones beyond the maximum
+    fn add_many(&mut self, kind: ProblemKind, field: &str, messages: Vec<String>) {
+        let n_messages = messages.len();
+        for message in messages.into_iter().take(MAX_PROBLEMS_PER_FIELD) {
+            self.add(kind, field, message);
+        }
+        if n_messages > MAX_PROBLEMS_PER_FIELD {
+            self.add(
+                kind,
+                field,
+                format!("{} more problems", n_messages - MAX_PROBLEMS_PER_FIELD),
+            );
+        }
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_valid() {
+            return write!(f, "LNAS is valid");
+        }
+        write!(f, "LNAS has {} problems", self.problems.len())?;
+        for problem in self.problems.iter() {
+            write!(f, "\n  {}", problem)?;
+        }
+        return Ok(());
+    }
+}
+
+fn is_finite(v: &Vec3f) -> bool {
+    return v.x.is_finite() && v.y.is_finite() && v.z.is_finite();
+}
+
+/// Check indices are below the number of elements they reference
+fn out_of_bounds_messages(indices: &Vec<u32>, n_elements: usize, name: &str) -> Vec<String> {
+    return indices
+        .iter()
+        .enumerate()
+        .filter(|(_, idx)| **idx as usize >= n_elements)
+        .map(|(pos, idx)| {
+            format!(
+                "index {} at position {} is beyond {} {}",
+                idx, pos, n_elements, name
+            )
+        })
+        .collect();
+}
+
+/// Check optional geometry array has one value (of `n_floats` f32) for each element
+fn check_geometry_array(
+    report: &mut ValidationReport,
+    lnas_obj: &LNAS,
+    field: &str,
+    array: &Option<ArrayLNAS>,
+    n_floats: usize,
+    n_expected: Option<usize>,
+    invalid_fields: &HashSet<String>,
+) {
+    let field = format!("geometry.{}", field);
+    let array = match array {
+        Some(array) if !invalid_fields.contains(&field) => array,
+        _ => return,
+    };
+    let decoded = lnas_obj.array_bytes(array).and_then(|b| match n_floats {
+        3 => Ok(bytes_to_vec3f(&b)?.len()),
+        _ => Ok(bytes_to_f32(&b)?.len()),
+    });
+    let len = match decoded {
+        Ok(len) => len,
+        Err(e) => {
+            report.add(ProblemKind::InvalidArray, &field, e.to_string());
+            return;
+        }
+    };
+    if let Some(n_expected) = n_expected {
+        if len != n_expected {
+            report.add(
+                ProblemKind::LengthMismatch,
+                &field,
+                format!("has {} values, expected {}", len, n_expected),
+            );
+        }
+    }
+}
+
+/// Decode all arrays of LNAS, checking their consistency
+pub fn validate_lnas(lnas_obj: &LNAS) -> ValidationReport {
+    let mut report = ValidationReport::default();
+    check_lnas(&mut report, lnas_obj, &HashSet::new());
+    return report;
+}
+
+/// Arrays of LNAS YAML content, as field path and base64 string
+fn get_value_arrays(value: &mut Value) -> Vec<(String, &mut Value)> {
+    let mut arrays: Vec<(String, &mut Value)> = Vec::new();
+    let mapping = match value.as_mapping_mut() {
+        Some(mapping) => mapping,
+        None => return arrays,
+    };
+    for (key, section) in mapping.iter_mut() {
+        let section_name = key.as_str().unwrap_or_default();
+        let fields = match (section_name, section.as_mapping_mut()) {
+            ("geometry" | "surfaces" | "features", Some(fields)) => fields,
+            _ => continue,
+        };
+        for (field_key, field) in fields.iter_mut() {
+            let path = format!(
+                "{}.{}",
+                section_name,
+                field_key.as_str().unwrap_or_default()
+            );
+            // Surfaces may be nested (current layout) or flat (up to v0.5)
+            let array = match field.is_mapping() && section_name == "surfaces" {
+                true => match field.get_mut("triangles_idxs") {
+                    Some(array) => array,
+                    None => continue,
+                },
+                false => field,
+            };
+            if array.is_string() {
+                arrays.push((path, array));
+            }
+        }
+    }
+    return arrays;
+}
+
+/// Validate LNAS YAML content, reporting arrays that are not valid base64 as invalid
+/// instead of failing to read them
+pub fn validate_lnas_value(mut value: Value) -> Result<ValidationReport, Box<dyn Error>> {
+    let mut report = ValidationReport::default();
+    let mut invalid_fields: HashSet<String> = HashSet::new();
+    for (field, array) in get_value_arrays(&mut value).into_iter() {
+        if let Err(e) = decode_bytes(array.as_str().unwrap()) {
+            report.add(ProblemKind::InvalidArray, &field, e.to_string());
+            // Replaced by empty array, so the other fields may still be read and checked
+            *array = Value::from("");
+            invalid_fields.insert(field);
+        }
+    }
+    let lnas_obj: LNAS = serde_yaml::from_value(value)?;
+    check_lnas(&mut report, &lnas_obj, &invalid_fields);
+    return Ok(report);
+}
+
+/// Read and validate LNAS file, in YAML or binary layout
+pub fn validate_lnas_file(filename: &path::Path) -> Result<ValidationReport, Box<dyn Error>> {
+    let content = fs::read(filename)?;
+    if is_lnasb(&content) {
+        return Ok(validate_lnas(&read_lnasb_bytes(&content)?));
+    }
+    return validate_lnas_value(serde_yaml::from_slice(&content)?);
+}
+
+/// Check LNAS arrays, skipping the invalid fields already reported
+fn check_lnas(report: &mut ValidationReport, lnas_obj: &LNAS, invalid_fields: &HashSet<String>) {
+    if let Err(e) = parse_version(&lnas_obj.version) {
+        report.add(ProblemKind::InvalidVersion, "version", e.to_string());
+    }
+
+    let n_vertices = match lnas_obj.get_vertices() {
+        _ if invalid_fields.contains("geometry.vertices") => None,
+        Ok(vertices) => {
+            let messages: Vec<String> = vertices
+                .iter()
+                .enumerate()
+                .filter(|(_, v)| !is_finite(v))
+                .map(|(i, v)| format!("vertex {} is ({}, {}, {})", i, v.x, v.y, v.z))
+                .collect();
+            report.add_many(ProblemKind::NonFiniteVertex, "geometry.vertices", messages);
+            Some(vertices.len())
+        }
+        Err(e) => {
+            report.add(
+                ProblemKind::InvalidArray,
+                "geometry.vertices",
+                e.to_string(),
+            );
+            None
+        }
+    };
+
+    let n_triangles = match lnas_obj.get_triangles() {
+        _ if invalid_fields.contains("geometry.triangles") => None,
+        Ok(triangles) => {
+            if let Some(n_vertices) = n_vertices {
+                let indices: Vec<u32> = triangles.iter().flat_map(|t| [t.x, t.y, t.z]).collect();
+                let messages = out_of_bounds_messages(&indices, n_vertices, "vertices");
+                report.add_many(
+                    ProblemKind::IndexOutOfBounds,
+                    "geometry.triangles",
+                    messages,
+                );
+            }
+            Some(triangles.len())
+        }
+        Err(e) => {
+            report.add(
+                ProblemKind::InvalidArray,
+                "geometry.triangles",
+                e.to_string(),
+            );
+            None
+        }
+    };
+
+    let geometry = &lnas_obj.geometry;
+    for (field, array, n_floats, n_expected) in [
+        (
+            "vertices_normals",
+            &geometry.vertices_normals,
+            3,
+            n_vertices,
+        ),
+        ("vertices_areas", &geometry.vertices_areas, 1, n_vertices),
+        (
+            "triangles_centroids",
+            &geometry.triangles_centroids,
+            3,
+            n_triangles,
+        ),
+        ("triangles_areas", &geometry.triangles_areas, 1, n_triangles),
+        (
+            "triangles_normals",
+            &geometry.triangles_normals,
+            3,
+            n_triangles,
+        ),
+    ] {
+        check_geometry_array(
+            report,
+            lnas_obj,
+            field,
+            array,
+            n_floats,
+            n_expected,
+            invalid_fields,
+        );
+    }
+
+    // Surface of each triangle, to find triangles in more than one surface
+    let mut triangles_surfaces: HashMap<u32, &str> = HashMap::new();
+    let mut surface_names: Vec<&String> = lnas_obj.surfaces.keys().collect();
+    surface_names.sort();
+    for surface_name in surface_names.into_iter() {
+        let field = format!("surfaces.{}", surface_name);
+        if invalid_fields.contains(&field) {
+            continue;
+        }
+        let surface = lnas_obj.surfaces.get(surface_name).unwrap();
+        let triangles_idxs = match lnas_obj.array_indices(&surface.triangles_idxs) {
+            Ok(triangles_idxs) => triangles_idxs,
+            Err(e) => {
+                report.add(ProblemKind::InvalidArray, &field, e.to_string());
+                continue;
+            }
+        };
+        if let Some(n_triangles) = n_triangles {
+            let messages = out_of_bounds_messages(&triangles_idxs, n_triangles, "triangles");
+            report.add_many(ProblemKind::IndexOutOfBounds, &field, messages);
+        }
+        let mut messages: Vec<String> = Vec::new();
+        for t_idx in triangles_idxs.iter() {
+            match triangles_surfaces.get(t_idx) {
+                Some(other_name) => messages.push(format!(
+                    "triangle {} is already in surface '{}'",
+                    t_idx, other_name
+                )),
+                None => {
+                    triangles_surfaces.insert(*t_idx, surface_name);
+                }
+            }
+        }
+        report.add_many(ProblemKind::DuplicatedTriangle, &field, messages);
+    }
+
+    if let Some(features) = &lnas_obj.features {
+        for (field, array) in [
+            ("features.edges", &features.edges),
+            ("features.corners", &features.corners),
+        ] {
+            if invalid_fields.contains(field) {
+                continue;
+            }
+            match lnas_obj.array_bytes(array).and_then(|b| bytes_to_u32(&b)) {
+                Ok(indices) => {
+                    if field == "features.edges" && indices.len() % 2 != 0 {
+                        report.add(
+                            ProblemKind::InvalidArray,
+                            field,
+                            format!("has {} indices, expected pairs", indices.len()),
+                        );
+                    }
+                    if let Some(n_vertices) = n_vertices {
+                        let messages = out_of_bounds_messages(&indices, n_vertices, "vertices");
+                        report.add_many(ProblemKind::IndexOutOfBounds, field, messages);
+                    }
+                }
+                Err(e) => report.add(ProblemKind::InvalidArray, field, e.to_string()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lagrangian::format::{encode_u32, encode_vec3f};
+    use crate::lagrangian::test_utils::get_lnas_obj;
+
+    fn problems_kinds(report: &ValidationReport) -> Vec<(ProblemKind, String)> {
+        return report
+            .problems
+            .iter()
+            .map(|p| (p.kind, p.field.clone()))
+            .collect();
+    }
+
+    #[test]
+    fn check_valid_lnas() {
+        let lnas_obj = get_lnas_obj(&["cube", "plane"]);
+        let report = validate_lnas(&lnas_obj);
+        assert!(report.is_valid(), "{}", report);
+    }
+
+    #[test]
+    fn check_invalid_arrays() {
+        let mut lnas_obj = get_lnas_obj(&["cube", "plane"]);
+        lnas_obj.version = "0.6".to_string();
+        // Truncated array, 14 bytes
+        lnas_obj.geometry.vertices = ArrayLNAS(vec![0u8; 14]);
+        lnas_obj.geometry.vertices_areas = Some(encode_u32(&vec![0; 3]));
+
+        let report = validate_lnas(&lnas_obj);
+        assert_eq!(
+            problems_kinds(&report),
+            [
+                (ProblemKind::InvalidVersion, "version".to_string()),
+                (ProblemKind::InvalidArray, "geometry.vertices".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn check_truncated_base64() {
+        let lnas_obj = get_lnas_obj(&["cube", "plane"]);
+        let mut value = serde_yaml::to_value(&lnas_obj).unwrap();
+        let report = validate_lnas_value(value.clone()).unwrap();
+        assert!(report.is_valid(), "{}", report);
+
+        let truncate = |field: &Value| {
+            let encoded = field.as_str().unwrap();
+            return Value::from(&encoded[..encoded.len() - 3]);
+        };
+        value["geometry"]["vertices"] = truncate(&value["geometry"]["vertices"]);
+        value["surfaces"]["plane"]["triangles_idxs"] =
+            truncate(&value["surfaces"]["plane"]["triangles_idxs"]);
+        let report = validate_lnas_value(value).unwrap();
+        // Triangles can't be checked against unknown vertices, so nothing else is reported
+        assert_eq!(
+            problems_kinds(&report),
+            [
+                (ProblemKind::InvalidArray, "geometry.vertices".to_string()),
+                (ProblemKind::InvalidArray, "surfaces.plane".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn check_invalid_indices() {
+        let mut lnas_obj = get_lnas_obj(&["cube", "plane"]);
+        let n_triangles = lnas_obj.get_triangles().unwrap().len() as u32;
+        let mut vertices = lnas_obj.get_vertices().unwrap();
+        vertices[0].x = f32::NAN;
+        lnas_obj.geometry.vertices = encode_vec3f(&vertices);
+        lnas_obj.geometry.vertices_areas = Some(encode_u32(&vec![0; 3]));
+        lnas_obj.surfaces.get_mut("cube").unwrap().triangles_idxs =
+            encode_u32(&vec![0, 1, n_triangles]);
+        lnas_obj.surfaces.get_mut("plane").unwrap().triangles_idxs = encode_u32(&vec![1]);
+
+        let report = validate_lnas(&lnas_obj);
+        assert_eq!(
+            problems_kinds(&report),
+            [
+                (
+                    ProblemKind::NonFiniteVertex,
+                    "geometry.vertices".to_string()
+                ),
+                (
+                    ProblemKind::LengthMismatch,
+                    "geometry.vertices_areas".to_string()
+                ),
+                (ProblemKind::IndexOutOfBounds, "surfaces.cube".to_string()),
+                (
+                    ProblemKind::DuplicatedTriangle,
+                    "surfaces.plane".to_string()
+                ),
+            ]
+        );
+        assert!(!report.is_valid());
+    }
+}
@@ -4,7 +4,9 @@ use serde::Serialize;
 use std::collections::{HashMap, HashSet};
 use std::fmt;
 
-/// Lagranngian node is defined by a position, with normal and area properties
+/// Lagranngian node is defined by a position.
+///
+/// Its normal and area depend on the joined triangles, see `lagrangian::nodes`
 #[derive(Serialize, Hash, PartialEq, Eq, Copy, Clone, Debug)]
 pub struct LagrangianVertice {
     pub pos: Vec3f,
@@ -1,15 +1,43 @@
 pub mod lagrangian {
+    pub mod attributes;
+    pub mod binary;
+    pub mod bvh;
+    pub mod cleanup;
+    pub mod compression;
+    pub mod encoding;
+    pub mod features;
     pub mod format;
+    pub mod holes;
+    pub mod intersection;
     pub mod join;
+    pub mod lattice;
+    pub mod mesh;
+    pub mod metadata;
+    pub mod migrate;
+    pub mod nodes;
+    pub mod orientation;
+    pub mod projection;
+    pub mod proximity;
+    pub mod quality;
+    pub mod quantization;
+    pub mod reader;
+    pub mod refine;
     pub mod save;
+    pub mod split;
+    pub mod stats;
+    #[cfg(test)]
+    pub mod test_utils;
     pub mod triangle;
+    pub mod validate;
     pub mod vertice;
 }
 
 pub mod cfg;
 pub mod stl {
+    pub mod origin;
     pub mod reader;
     pub mod surfaces;
     pub mod triangle;
+    pub mod writer;
 }
 pub mod utils;
@@ -1,26 +1,71 @@
 pub mod lagrangian {
+    pub mod attributes;
+    pub mod binary;
+    pub mod bvh;
+    pub mod cleanup;
+    pub mod compression;
+    pub mod encoding;
+    pub mod features;
     pub mod format;
+    pub mod holes;
+    pub mod intersection;
     pub mod join;
+    pub mod lattice;
+    pub mod mesh;
+    pub mod metadata;
+    pub mod migrate;
+    pub mod nodes;
+    pub mod orientation;
+    pub mod projection;
+    pub mod proximity;
+    pub mod quality;
+    pub mod quantization;
+    pub mod reader;
+    pub mod refine;
     pub mod save;
+    pub mod split;
+    pub mod stats;
+    #[cfg(test)]
+    pub mod test_utils;
     pub mod triangle;
+    pub mod validate;
     pub mod vertice;
 }
 
 pub mod stl {
+    pub mod origin;
     pub mod reader;
     pub mod surfaces;
     pub mod triangle;
+    pub mod writer;
 }
 pub mod cfg;
 pub mod utils;
 
-use cfg::Args;
+use cfg::{Args, Command};
 use clap::Parser;
 use std::path;
-use stl::surfaces::get_surfaces;
+use stl::surfaces::{get_surfaces_with_criteria, print_rejected_triangles};
 
-fn generate_lnas(args: &Args) {
-    let (triangles, surfaces) = get_surfaces(&args.all_stls());
+/// Generate LNAS from arguments, returning if all requested checks passed
+fn generate_lnas(args: &Args) -> bool {
+    let mut checks_passed = true;
+    let all_stls = args.all_stls();
+    let mut origin_shift: Option<[f64; 3]> = None;
+    if args.origin_shift {
+        let shift = stl::origin::get_origin_shift(&all_stls);
+        println!(
+            "Shifting geometry origin by ({}, {}, {})",
+            shift[0], shift[1], shift[2]
+        );
+        origin_shift = Some(shift);
+    }
+    let (triangles, surfaces, rejected_triangles) = get_surfaces_with_criteria(
+        &all_stls,
+        &args.degeneracy_criteria(),
+        &origin_shift.unwrap_or(stl::origin::NO_SHIFT),
+    );
+    print_rejected_triangles(&rejected_triangles, 10);
 
     let lagrangian_vertices = lagrangian::vertice::generate_lagrangian_vertices(&triangles);
     let lagrangian_triangles =
@@ -28,29 +73,307 @@ fn generate_lnas(args: &Args) {
 
     let (joined_vertices, joined_triangles) =
         lagrangian::join::join_information(&lagrangian_vertices, &lagrangian_triangles);
-    let lnas_obj =
+
+    let (mut joined_vertices, mut joined_triangles, mut surfaces, cleanup_report) =
+        lagrangian::cleanup::remove_invalid_triangles(
+            &joined_vertices,
+            &joined_triangles,
+            &surfaces,
+        );
+    if cleanup_report.has_removed() {
+        println!("{}", cleanup_report);
+    }
+
+    if args.fix_orientation {
+        let orientation_report =
+            lagrangian::orientation::orient_triangles(&joined_vertices, &mut joined_triangles);
+        println!("{}", orientation_report);
+    }
+
+    if let Some(max_perimeter) = args.fill_holes {
+        let holes_surface = match args.filled_holes_surface {
+            true => Some(lagrangian::holes::FILLED_HOLES_SURFACE),
+            false => None,
+        };
+        let fill_holes_report = lagrangian::holes::fill_holes(
+            &mut joined_vertices,
+            &mut joined_triangles,
+            &mut surfaces,
+            max_perimeter,
+            holes_surface,
+            args.feature_angle,
+        );
+        println!("{}", fill_holes_report);
+    }
+
+    // Detected on the final topology, so refinement keeps the features it splits
+    let mut features: Option<lagrangian::features::FeatureEdges> = None;
+    if args.save_features || args.refinement.is_some() {
+        let detected_features = lagrangian::features::detect_feature_edges(
+            &joined_vertices,
+            &joined_triangles,
+            args.feature_angle,
+        );
+        println!("{}", detected_features);
+        features = Some(detected_features);
+    }
+
+    if let Some(refinement_filename) = &args.refinement {
+        let refinement_cfg =
+            lagrangian::refine::RefinementCfg::from_file(path::Path::new(refinement_filename))
+                .unwrap_or_else(|e| {
+                    panic!("Unable to read refinement configuration. Error: {}", e)
+                });
+        let refinement_report = lagrangian::refine::refine_surfaces(
+            &mut joined_vertices,
+            &mut joined_triangles,
+            &mut surfaces,
+            features.as_mut(),
+            &refinement_cfg,
+        );
+        println!("{}", refinement_report);
+    }
+
+    // Split after name-keyed configurations are applied to the original surfaces
+    let mut split_report: Option<lagrangian::split::SplitReport> = None;
+    if let Some(split_mode) = args.split_surfaces {
+        let report;
+        (surfaces, report) = lagrangian::split::split_surfaces(
+            &joined_vertices,
+            &joined_triangles,
+            &surfaces,
+            split_mode,
+            args.feature_angle,
+        );
+        println!("{}", report);
+        split_report = Some(report);
+    }
+
+    let mesh_quality = lagrangian::quality::analyze_mesh(&joined_vertices, &joined_triangles);
+    println!("{}", mesh_quality);
+
+    if args.check_intersections {
+        let intersection_report = lagrangian::intersection::find_intersections(
+            &joined_vertices,
+            &joined_triangles,
+            &surfaces,
+        );
+        println!("{}", intersection_report);
+        checks_passed &= intersection_report.is_valid();
+        if let Some(stl_filename) = &args.intersections_stl {
+            let stl_triangles = lagrangian::intersection::get_intersecting_stl_triangles(
+                &joined_vertices,
+                &joined_triangles,
+                &intersection_report,
+            );
+            stl::writer::write_stl(path::Path::new(stl_filename), &stl_triangles)
+                .unwrap_or_else(|e| panic!("Saving intersections STL error. Error: {}", e));
+        }
+    }
+
+    if let Some(threshold) = args.proximity {
+        let proximity_report = lagrangian::proximity::get_surfaces_proximity(
+            &joined_vertices,
+            &joined_triangles,
+            &surfaces,
+            threshold,
+        );
+        println!("{}", proximity_report);
+    }
+
+    if let Some(dx) = args.dx {
+        let lattice_report = lagrangian::lattice::check_lattice(
+            &joined_vertices,
+            &joined_triangles,
+            &surfaces,
+            dx,
+            args.max_dx_ratio,
+        );
+        println!("{}", lattice_report);
+        checks_passed &= lattice_report.is_valid();
+    }
+
+    if args.stats {
+        let stats_report = lagrangian::stats::get_stats(
+            &joined_vertices,
+            &joined_triangles,
+            &surfaces,
+            &origin_shift.unwrap_or(stl::origin::NO_SHIFT),
+        );
+        println!("{}", stats_report);
+        lagrangian::stats::save_stats(path::Path::new(&args.stats_filename()), &stats_report)
+            .unwrap_or_else(|e| panic!("Saving stats error. Error: {}", e));
+    }
+
+    if let Some(direction) = args.projection_direction() {
+        let projection_report = lagrangian::projection::get_projected_area(
+            &joined_vertices,
+            &joined_triangles,
+            &surfaces,
+            direction,
+            args.projection_resolution,
+        );
+        println!("{}", projection_report);
+    }
+
+    // Close indices of neighbour triangles make the encoded differences small
+    if args.indices_encoding.is_some() {
+        (joined_vertices, joined_triangles) = lagrangian::join::reorder_vertices(
+            &joined_vertices,
+            &joined_triangles,
+            features.as_mut(),
+        );
+    }
+
+    let mut lnas_obj =
         lagrangian::format::get_lnas_obj_save(&joined_vertices, &joined_triangles, &surfaces);
 
-    let lnas_filename = path::Path::new(&args.output);
+    if let Some(shift) = &origin_shift {
+        lnas_obj.set_origin_shift(shift);
+    }
+    if let Some(attributes_filename) = &args.surfaces_attributes {
+        let mut attributes_cfg = lagrangian::attributes::SurfacesAttributesCfg::from_file(
+            path::Path::new(attributes_filename),
+        )
+        .unwrap_or_else(|e| panic!("Unable to read surfaces attributes. Error: {}", e));
+        if let Some(split_report) = &split_report {
+            attributes_cfg.apply_split(split_report);
+        }
+        lnas_obj.set_surfaces_attributes(lagrangian::attributes::get_surfaces_attributes(
+            &attributes_cfg,
+            &surfaces,
+        ));
+    }
+    if args.save_features {
+        if let Some(features) = &features {
+            lnas_obj.set_features(features);
+        }
+    }
+    if let Some(weighting) = args.vertices_normals {
+        let normals =
+            lagrangian::nodes::get_vertices_normals(&joined_vertices, &joined_triangles, weighting);
+        lnas_obj.set_vertices_normals(&normals);
+    }
+    if let Some(method) = args.vertices_areas {
+        let areas =
+            lagrangian::nodes::get_vertices_areas(&joined_vertices, &joined_triangles, method);
+        lnas_obj.set_vertices_areas(&areas);
+    }
+    if args.triangles_properties {
+        let centroids =
+            lagrangian::nodes::get_triangles_centroids(&joined_vertices, &joined_triangles);
+        let areas = lagrangian::nodes::get_triangles_areas(&joined_vertices, &joined_triangles);
+        let normals = lagrangian::nodes::get_triangles_normals(&joined_vertices, &joined_triangles);
+        lnas_obj.set_triangles_properties(&centroids, &areas, &normals);
+    }
+
+    if let Some(dtype) = args.quantize_vertices {
+        let quantization_report = lnas_obj
+            .quantize_vertices(dtype)
+            .unwrap_or_else(|e| panic!("Quantizing lnas vertices error. Error: {}", e));
+        println!("{}", quantization_report);
+    }
+
+    if let Some(encoding) = args.indices_encoding {
+        let encoding_report = lnas_obj
+            .encode_indices(encoding)
+            .unwrap_or_else(|e| panic!("Encoding lnas indices error. Error: {}", e));
+        println!("{}", encoding_report);
+    }
+
+    if let Some(compression) = args.compression {
+        let compression_report = lnas_obj
+            .compress(compression)
+            .unwrap_or_else(|e| panic!("Compressing lnas error. Error: {}", e));
+        println!("{}", compression_report);
+    }
+
+    if args.metadata {
+        let metadata = lagrangian::metadata::MetadataLNAS::new(
+            &args.all_stls(),
+            std::env::args().collect(),
+            args.units.clone(),
+        )
+        .unwrap_or_else(|e| panic!("Generating metadata error. Error: {}", e));
+        lnas_obj
+            .set_metadata(metadata)
+            .unwrap_or_else(|e| panic!("Setting metadata error. Error: {}", e));
+    }
+
+    let lnas_filename = path::Path::new(args.output());
 
     lagrangian::save::save_lnas(lnas_filename, &lnas_obj)
         .unwrap_or_else(|e| panic!("Saving lnas error. Error: {}", e));
+
+    return checks_passed;
+}
+
+/// Migrate LNAS to current version, returning if it succeeded
+fn migrate_lnas(input: &str, output: &str, overwrite: bool) -> bool {
+    let output_filename = path::Path::new(output);
+    if output_filename.exists() && !overwrite {
+        println!(
+            "File '{}' already exists. Add '--overwrite' if you wish to overwrite it",
+            output
+        );
+        return false;
+    }
+    let (lnas_obj, migration_report) =
+        match lagrangian::migrate::migrate_lnas_file(path::Path::new(input)) {
+            Ok(migrated) => migrated,
+            Err(e) => {
+                println!("Unable to migrate '{}'. Error: {}", input, e);
+                return false;
+            }
+        };
+    println!("{}", migration_report);
+    lagrangian::save::save_lnas(output_filename, &lnas_obj)
+        .unwrap_or_else(|e| panic!("Saving lnas error. Error: {}", e));
+    return true;
+}
+
+/// Validate LNAS, returning if it has no problems
+fn validate_lnas(input: &str) -> bool {
+    let validation_report = match lagrangian::validate::validate_lnas_file(path::Path::new(input)) {
+        Ok(validation_report) => validation_report,
+        Err(e) => {
+            println!("Unable to read '{}'. Error: {}", input, e);
+            return false;
+        }
+    };
+    println!("{}", validation_report);
+    return validation_report.is_valid();
 }
 
 fn main() {
     let args = Args::parse();
 
+    if let Some(command) = &args.command {
+        let succeeded = match command {
+            Command::Migrate {
+                input,
+                output,
+                overwrite,
+            } => migrate_lnas(input, output, *overwrite),
+            Command::Validate { input } => validate_lnas(input),
+        };
+        if !succeeded {
+            std::process::exit(1);
+        }
+        return;
+    }
+
     if args.file.len() == 0 && args.dir.len() == 0 {
         println!("No file or dir to convert");
         return;
     }
 
-    let lnas_filename = path::Path::new(&args.output);
+    let lnas_filename = path::Path::new(args.output());
     if lnas_filename.exists() {
         if !args.overwrite {
             panic!(
                 "File '{}' already exists. Add '--overwrite' if you wish to overwrite it",
-                args.output
+                args.output()
             );
         } else {
             println!("Overwriting file...");
@@ -62,6 +385,10 @@ fn main() {
             .unwrap_or_else(|e| println!("Unable to save STL in its output folder. Error: {}", e));
     }
 
-    generate_lnas(&args);
+    let checks_passed = generate_lnas(&args);
     println!("Generated!");
+    if !checks_passed {
+        println!("Some checks failed, see report above");
+        std::process::exit(1);
+    }
 }
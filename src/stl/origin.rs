@@ -0,0 +1,88 @@
+use crate::stl::reader::read_stl_points;
+use std::{collections::HashMap, path};
+
+/// Shift of geometry that is not shifted
+pub const NO_SHIFT: [f64; 3] = [0f64; 3];
+
+/// Shift that moves STLs bounding box center to origin, rounded to integer values.
+///
+/// Large coordinates (such as geo-referenced terrains) lose precision in f32 operations,
+/// so the geometry is processed and saved relative to this shift. It is computed in f64 from
+/// the points as stored in the STLs, to be subtracted before any f32 operation
+pub fn get_origin_shift(files: &HashMap<String, path::PathBuf>) -> [f64; 3] {
+    let mut min = [f64::INFINITY; 3];
+    let mut max = [f64::NEG_INFINITY; 3];
+    for filename in files.values() {
+        for p in read_stl_points(filename).iter() {
+            for i in 0..3 {
+                min[i] = min[i].min(p[i]);
+                max[i] = max[i].max(p[i]);
+            }
+        }
+    }
+    if min[0] > max[0] {
+        return NO_SHIFT;
+    }
+    return [0, 1, 2].map(|i| ((min[i] + max[i]) / 2f64).round());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stl::reader::{read_stl, read_stl_with_criteria};
+    use crate::stl::triangle::{DegeneracyCriteria, TriangleSTL};
+    use crate::stl::writer::write_stl;
+    use crate::utils::Vec3f;
+
+    fn get_files(name: &str, filename: &str) -> HashMap<String, path::PathBuf> {
+        let mut files: HashMap<String, path::PathBuf> = HashMap::new();
+        files.insert(name.to_string(), path::Path::new(filename).to_owned());
+        return files;
+    }
+
+    #[test]
+    fn check_origin_shift_terrain() {
+        let filename = path::Path::new("examples/stl/terrain.stl").to_owned();
+        let triangles = read_stl(&filename);
+        let shift = get_origin_shift(&get_files("terrain", "examples/stl/terrain.stl"));
+        assert_eq!(shift[0], shift[0].round());
+
+        let (shifted, _) =
+            read_stl_with_criteria(&filename, &DegeneracyCriteria::default(), &shift);
+        assert_eq!(shifted.len(), triangles.len());
+        for (t0, t1) in triangles.iter().zip(shifted.iter()) {
+            assert!((t1.point0.x as f64 + shift[0] - t0.point0.x as f64).abs() < 1e-4);
+            assert_eq!(t1.normal, t0.normal);
+            assert!((t1.area() - t0.area()).abs() <= 1e-4 * t0.area().max(1f32));
+        }
+    }
+
+    #[test]
+    fn check_origin_shift_beyond_f32_integers() {
+        // Above 2^24 f32 only represents even integers, so the center 20000003 is not an f32
+        let point = |x: f32, y: f32| Vec3f { x, y, z: 0f32 };
+        let triangles = vec![TriangleSTL::new(
+            point(20000000f32, 0f32),
+            point(20000006f32, 0f32),
+            point(20000000f32, 2f32),
+            Vec3f {
+                x: 0f32,
+                y: 0f32,
+                z: 1f32,
+            },
+        )];
+        let filename = "output/origin/large.stl";
+        write_stl(path::Path::new(filename), &triangles).unwrap();
+
+        let shift = get_origin_shift(&get_files("large", filename));
+        assert_eq!(shift, [20000003f64, 1f64, 0f64]);
+        let (shifted, _) = read_stl_with_criteria(
+            &path::Path::new(filename).to_owned(),
+            &DegeneracyCriteria::default(),
+            &shift,
+        );
+        assert_eq!(shifted[0].point0.x, -3f32);
+        assert_eq!(shifted[0].point1.x, 3f32);
+        assert_eq!(shifted[0].point2.y, 1f32);
+    }
+}
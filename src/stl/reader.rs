@@ -1,4 +1,7 @@
-use crate::stl::triangle::TriangleSTL;
+use crate::stl::origin::NO_SHIFT;
+use crate::stl::triangle::{
+    filter_degenerated_triangles, DegeneracyCriteria, RejectedTriangle, TriangleSTL,
+};
 use crate::utils::{bytes_to_u32_le, Vec3f};
 use std::{convert::TryInto, fs, path};
 
@@ -11,10 +14,39 @@ fn read_file(filename: &path::PathBuf) -> Vec<u8> {
 }
 
 pub fn read_stl(filename: &path::PathBuf) -> Vec<TriangleSTL> {
+    let (triangles, rejected_triangles) =
+        read_stl_with_criteria(filename, &DegeneracyCriteria::default(), &NO_SHIFT);
+    if !rejected_triangles.is_empty() {
+        println!(
+            "Found {} invalid triangles in STL, they were not added to LNAS",
+            rejected_triangles.len()
+        );
+    }
+    return triangles;
+}
+
+/// Read STL triangles, with `origin_shift` subtracted from their points, separating the ones
+/// considered degenerated by the criteria
+pub fn read_stl_with_criteria(
+    filename: &path::PathBuf,
+    criteria: &DegeneracyCriteria,
+    origin_shift: &[f64; 3],
+) -> (Vec<TriangleSTL>, Vec<RejectedTriangle>) {
     let stl_content = read_file(filename);
     let n_triangles = number_of_triangles(&stl_content);
-    let triangles = triangles_from_stl(&stl_content, n_triangles);
-    return triangles;
+    let triangles = triangles_from_stl(&stl_content, n_triangles, origin_shift);
+    return filter_degenerated_triangles(triangles, criteria);
+}
+
+/// Points of all STL triangles, as stored in file
+pub fn read_stl_points(filename: &path::PathBuf) -> Vec<[f64; 3]> {
+    let stl_content = read_file(filename);
+    let n_triangles = number_of_triangles(&stl_content);
+    return triangles_from_stl(&stl_content, n_triangles, &NO_SHIFT)
+        .iter()
+        .flat_map(|t| [t.point0, t.point1, t.point2])
+        .map(|p| [p.x as f64, p.y as f64, p.z as f64])
+        .collect();
 }
 
 fn number_of_triangles(stl_content: &Vec<u8>) -> u32 {
@@ -26,40 +58,45 @@ fn number_of_triangles(stl_content: &Vec<u8>) -> u32 {
     return n_triangles;
 }
 
-fn bytes_to_triangle(b: &[u8; TRIANGLE_BYTES_SIZE]) -> TriangleSTL {
+/// Subtract shift in f64, so the shifted point is the closest f32 to its exact value
+fn shift_point(point: Vec3f, origin_shift: &[f64; 3]) -> Vec3f {
+    return Vec3f {
+        x: (point.x as f64 - origin_shift[0]) as f32,
+        y: (point.y as f64 - origin_shift[1]) as f32,
+        z: (point.z as f64 - origin_shift[2]) as f32,
+    };
+}
+
+fn bytes_to_triangle(b: &[u8; TRIANGLE_BYTES_SIZE], origin_shift: &[f64; 3]) -> TriangleSTL {
     let normal = Vec3f::from_bytes_le(&b[..12].try_into().expect("Invalid file format (point 0)"));
     let point0 =
         Vec3f::from_bytes_le(&b[12..24].try_into().expect("Invalid file format (point 1)"));
     let point1 =
         Vec3f::from_bytes_le(&b[24..36].try_into().expect("Invalid file format (point 2)"));
     let point2 = Vec3f::from_bytes_le(&b[36..48].try_into().expect("Invalid file format (normal)"));
+    let point0 = shift_point(point0, origin_shift);
+    let point1 = shift_point(point1, origin_shift);
+    let point2 = shift_point(point2, origin_shift);
     // 2 points are for attribute byte count
     return TriangleSTL::new(point0, point1, point2, normal);
 }
 
-fn triangles_from_stl(stl_content: &Vec<u8>, n_triangles: u32) -> Vec<TriangleSTL> {
+fn triangles_from_stl(
+    stl_content: &Vec<u8>,
+    n_triangles: u32,
+    origin_shift: &[f64; 3],
+) -> Vec<TriangleSTL> {
     // + 4 due to triangle numbers
     let start_byte = HEADER_BYTES_SIZE + 4;
-    let mut all_triangles: Vec<TriangleSTL> = Vec::new();
-    let mut n_invalid_triangles = 0;
+    let mut all_triangles: Vec<TriangleSTL> = Vec::with_capacity(n_triangles as usize);
     for i in 0..n_triangles {
         let curr_byte_idx = start_byte + TRIANGLE_BYTES_SIZE * (i as usize);
         let curr_triangle_bytes: &[u8; TRIANGLE_BYTES_SIZE] = stl_content
             [curr_byte_idx..curr_byte_idx + TRIANGLE_BYTES_SIZE]
             .try_into()
             .expect("Invalid triangle bytes");
-        let triangle = bytes_to_triangle(curr_triangle_bytes);
-        if triangle.check_area_valid() {
-            all_triangles.push(triangle);
-        } else {
-            n_invalid_triangles += 1;
-        }
-    }
-    if n_invalid_triangles > 0 {
-        println!(
-            "Found {} invalid triangles in STL, they were not added to LNAS",
-            n_invalid_triangles
-        );
+        let triangle = bytes_to_triangle(curr_triangle_bytes, origin_shift);
+        all_triangles.push(triangle);
     }
 
     return all_triangles;
@@ -78,6 +115,22 @@ mod tests {
         assert_eq!(triangles.len(), 6 * 2);
     }
 
+    #[test]
+    fn can_read_stl_with_criteria() {
+        let str_filename = String::from("examples/stl/cylinder.stl");
+        let filename = path::Path::new(str_filename.as_str()).to_owned();
+        let n_triangles = number_of_triangles(&read_file(&filename));
+        let criteria = DegeneracyCriteria {
+            min_angle: Some(5f32),
+            ..Default::default()
+        };
+        let (triangles, rejected) = read_stl_with_criteria(&filename, &criteria, &NO_SHIFT);
+        assert_eq!(triangles.len() + rejected.len(), n_triangles as usize);
+        for r in rejected.iter() {
+            assert!(r.triangle.min_angle() < 5f32);
+        }
+    }
+
     #[test]
     fn can_read_stl_terrain() {
         let str_filename = String::from("examples/stl/terrain.stl");
@@ -1,18 +1,75 @@
 use std::{collections::HashMap, path, string::String};
 
-use crate::stl::reader::read_stl;
-use crate::stl::triangle::TriangleSTL;
+use crate::stl::origin::NO_SHIFT;
+use crate::stl::reader::read_stl_with_criteria;
+use crate::stl::triangle::{DegeneracyCriteria, RejectedTriangle, TriangleSTL};
 
-fn get_stl_triangles(stl_filename: &path::PathBuf) -> Vec<TriangleSTL> {
-    let triangles = read_stl(stl_filename);
-    return triangles;
+/// Triangles rejected by criteria, by surface name
+pub type SurfacesRejected = HashMap<String, Vec<RejectedTriangle>>;
+
+fn get_stl_triangles(
+    stl_filename: &path::PathBuf,
+    criteria: &DegeneracyCriteria,
+    origin_shift: &[f64; 3],
+) -> (Vec<TriangleSTL>, Vec<RejectedTriangle>) {
+    let (triangles, rejected_triangles) =
+        read_stl_with_criteria(stl_filename, criteria, origin_shift);
+    return (triangles, rejected_triangles);
 }
 
 pub fn get_surfaces(
     files: &HashMap<String, path::PathBuf>,
 ) -> (Vec<TriangleSTL>, HashMap<String, Vec<u32>>) {
+    let (all_triangles, surfaces_triangles, rejected_triangles) =
+        get_surfaces_with_criteria(files, &DegeneracyCriteria::default(), &NO_SHIFT);
+    let n_rejected: usize = rejected_triangles.values().map(|r| r.len()).sum();
+    if n_rejected > 0 {
+        println!(
+            "Found {} invalid triangles in STL, they were not added to LNAS",
+            n_rejected
+        );
+    }
+    return (all_triangles, surfaces_triangles);
+}
+
+/// Print rejected triangles of each surface, listing up to `max_listed` of them
+pub fn print_rejected_triangles(rejected_triangles: &SurfacesRejected, max_listed: usize) {
+    let mut surface_names: Vec<&String> = rejected_triangles.keys().collect();
+    surface_names.sort();
+    for surface_name in surface_names.into_iter() {
+        let surface_rejected = rejected_triangles.get(surface_name).unwrap();
+        if surface_rejected.is_empty() {
+            continue;
+        }
+        println!(
+            "Surface '{}' has {} degenerated triangles, they were not added to LNAS",
+            surface_name,
+            surface_rejected.len()
+        );
+        for r in surface_rejected.iter().take(max_listed) {
+            println!("  {}", r);
+        }
+        if surface_rejected.len() > max_listed {
+            println!("  ... and {} more", surface_rejected.len() - max_listed);
+        }
+    }
+}
+
+/// Read surfaces triangles, shifted by `origin_shift`, filtering the ones degenerated by criteria.
+///
+/// Degenerated triangles are returned by surface name.
+pub fn get_surfaces_with_criteria(
+    files: &HashMap<String, path::PathBuf>,
+    criteria: &DegeneracyCriteria,
+    origin_shift: &[f64; 3],
+) -> (
+    Vec<TriangleSTL>,
+    HashMap<String, Vec<u32>>,
+    SurfacesRejected,
+) {
     let mut all_triangles: Vec<TriangleSTL> = Vec::new();
     let mut surfaces_triangles: HashMap<String, Vec<u32>> = HashMap::new();
+    let mut surfaces_rejected: SurfacesRejected = HashMap::new();
 
     // Do this to be deterministic
     let mut surface_names: Vec<&String> = files.keys().into_iter().collect();
@@ -21,7 +78,8 @@ pub fn get_surfaces(
     for surface_name in surface_names.into_iter() {
         // STL triangles
         let stl_filename = files.get(surface_name).unwrap();
-        let mut stl_triangles = get_stl_triangles(stl_filename);
+        let (mut stl_triangles, stl_rejected) =
+            get_stl_triangles(stl_filename, criteria, origin_shift);
         // Index of these STL triangles when comparing to list of triangles
         let triangles_idxs_range = all_triangles.len()..all_triangles.len() + stl_triangles.len();
         let triangles_idxs_u32: Vec<u32> = triangles_idxs_range
@@ -32,8 +90,9 @@ pub fn get_surfaces(
         all_triangles.append(&mut stl_triangles);
         // Inset surface triangles indexes in hash map
         surfaces_triangles.insert(surface_name.to_owned(), triangles_idxs_u32);
+        surfaces_rejected.insert(surface_name.to_owned(), stl_rejected);
     }
-    return (all_triangles, surfaces_triangles);
+    return (all_triangles, surfaces_triangles, surfaces_rejected);
 }
 
 #[cfg(test)]
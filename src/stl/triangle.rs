@@ -33,15 +33,171 @@ impl TriangleSTL {
         self.point2.transform(factor, offset);
     }
 
-    pub fn check_area_valid(self) -> bool {
+    pub fn area(self) -> f32 {
         let u: utils::Vec3f = self.point0 - self.point1;
         let v: utils::Vec3f = self.point0 - self.point2;
-        let area = u.cross(v).norm() / 2.0;
-        if area < 1e-5 {
-            return false;
+        return u.cross(v).norm() / 2.0;
+    }
+
+    pub fn centroid(self) -> utils::Vec3f {
+        let mut centroid = self.point0 + self.point1 + self.point2;
+        centroid.divide(3f32);
+        return centroid;
+    }
+
+    fn edges_lengths(self) -> [f32; 3] {
+        return [
+            (self.point1 - self.point0).norm(),
+            (self.point2 - self.point1).norm(),
+            (self.point0 - self.point2).norm(),
+        ];
+    }
+
+    /// Smallest internal angle, in degrees (NaN if triangle has a zero length edge)
+    pub fn min_angle(self) -> f32 {
+        let [a, b, c] = self.edges_lengths();
+        // Law of cosines for the angle opposite to each edge
+        let angles = [(a, b, c), (b, c, a), (c, a, b)].map(|(opp, s0, s1)| {
+            let cos = (s0 * s0 + s1 * s1 - opp * opp) / (2f32 * s0 * s1);
+            cos.clamp(-1f32, 1f32).acos().to_degrees()
+        });
+        if angles.iter().any(|a| a.is_nan()) {
+            return f32::NAN;
+        }
+        return angles.into_iter().fold(f32::MAX, f32::min);
+    }
+
+    /// Longest edge compared to triangle's height, normalized to be 1 for equilateral triangles
+    pub fn aspect_ratio(self) -> f32 {
+        let max_edge = self.edges_lengths().into_iter().fold(0f32, f32::max);
+        return 3f32.sqrt() * max_edge * max_edge / (4f32 * self.area());
+    }
+
+    /// Check if triangle is degenerated by any of the criteria.
+    ///
+    /// `mean_area` is the mean area of triangles in the same surface, used for relative area.
+    /// NaN values (from zero length edges or invalid coordinates) are considered degenerated.
+    pub fn check_degeneracy(
+        self,
+        criteria: &DegeneracyCriteria,
+        mean_area: f32,
+    ) -> Option<Degeneracy> {
+        let area = self.area();
+        if area.is_nan() || area < criteria.min_area {
+            return Some(Degeneracy::Area);
+        }
+        if let Some(min_relative_area) = criteria.min_relative_area {
+            if area < min_relative_area * mean_area {
+                return Some(Degeneracy::RelativeArea);
+            }
+        }
+        if let Some(min_angle) = criteria.min_angle {
+            let angle = self.min_angle();
+            if angle.is_nan() || angle < min_angle {
+                return Some(Degeneracy::Angle);
+            }
+        }
+        if let Some(max_aspect_ratio) = criteria.max_aspect_ratio {
+            let aspect_ratio = self.aspect_ratio();
+            if aspect_ratio.is_nan() || aspect_ratio > max_aspect_ratio {
+                return Some(Degeneracy::AspectRatio);
+            }
+        }
+        return None;
+    }
+}
+
+/// Criteria to consider a triangle degenerated and not add it to LNAS
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DegeneracyCriteria {
+    /// Minimum absolute area
+    pub min_area: f32,
+    /// Minimum area relative to the mean area of the surface triangles
+    pub min_relative_area: Option<f32>,
+    /// Minimum internal angle, in degrees
+    pub min_angle: Option<f32>,
+    /// Maximum aspect ratio (1 for equilateral triangles)
+    pub max_aspect_ratio: Option<f32>,
+}
+
+impl Default for DegeneracyCriteria {
+    fn default() -> Self {
+        return DegeneracyCriteria {
+            min_area: 1e-5,
+            min_relative_area: None,
+            min_angle: None,
+            max_aspect_ratio: None,
+        };
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Degeneracy {
+    Area,
+    RelativeArea,
+    Angle,
+    AspectRatio,
+}
+
+impl fmt::Display for Degeneracy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            Degeneracy::Area => "area below minimum",
+            Degeneracy::RelativeArea => "area relative to mean below minimum",
+            Degeneracy::Angle => "angle below minimum",
+            Degeneracy::AspectRatio => "aspect ratio above maximum",
+        };
+        return write!(f, "{}", reason);
+    }
+}
+
+/// Triangle not added to LNAS because it's degenerated
+#[derive(Clone, Copy)]
+pub struct RejectedTriangle {
+    /// Index of triangle in its STL
+    pub stl_idx: usize,
+    pub triangle: TriangleSTL,
+    pub reason: Degeneracy,
+}
+
+impl fmt::Display for RejectedTriangle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(
+            f,
+            "triangle {} at {} with area {:.4e}: {}",
+            self.stl_idx,
+            self.triangle.centroid(),
+            self.triangle.area(),
+            self.reason
+        );
+    }
+}
+
+/// Split triangles in valid and degenerated ones
+pub fn filter_degenerated_triangles(
+    triangles: Vec<TriangleSTL>,
+    criteria: &DegeneracyCriteria,
+) -> (Vec<TriangleSTL>, Vec<RejectedTriangle>) {
+    let valid_areas: Vec<f32> = triangles
+        .iter()
+        .map(|t| t.area())
+        .filter(|a| *a >= criteria.min_area)
+        .collect();
+    let mean_area = valid_areas.iter().sum::<f32>() / (valid_areas.len().max(1) as f32);
+
+    let mut valid_triangles: Vec<TriangleSTL> = Vec::with_capacity(triangles.len());
+    let mut rejected_triangles: Vec<RejectedTriangle> = Vec::new();
+    for (stl_idx, triangle) in triangles.into_iter().enumerate() {
+        match triangle.check_degeneracy(criteria, mean_area) {
+            None => valid_triangles.push(triangle),
+            Some(reason) => rejected_triangles.push(RejectedTriangle {
+                stl_idx,
+                triangle,
+                reason,
+            }),
         }
-        return true;
     }
+    return (valid_triangles, rejected_triangles);
 }
 
 impl PartialEq for TriangleSTL {
@@ -181,6 +337,97 @@ mod tests {
         }
     }
 
+    fn get_triangle(point1: utils::Vec3f, point2: utils::Vec3f) -> TriangleSTL {
+        let point0 = utils::Vec3f {
+            x: 0f32,
+            y: 0f32,
+            z: 0f32,
+        };
+        let normal = utils::Vec3f {
+            x: 0f32,
+            y: 0f32,
+            z: 1f32,
+        };
+        return TriangleSTL::new(point0, point1, point2, normal);
+    }
+
+    #[test]
+    fn check_degeneracy_criteria() {
+        let right_triangle = get_triangle(
+            utils::Vec3f {
+                x: 1f32,
+                y: 0f32,
+                z: 0f32,
+            },
+            utils::Vec3f {
+                x: 0f32,
+                y: 1f32,
+                z: 0f32,
+            },
+        );
+        let sliver_triangle = get_triangle(
+            utils::Vec3f {
+                x: 1f32,
+                y: 0f32,
+                z: 0f32,
+            },
+            utils::Vec3f {
+                x: 0.5f32,
+                y: 0.01f32,
+                z: 0f32,
+            },
+        );
+        assert_almost_equal!(right_triangle.min_angle(), 45f32, 1e-4f32);
+        assert_almost_equal!(right_triangle.aspect_ratio(), 3f32.sqrt(), 1e-4f32);
+
+        let criteria = DegeneracyCriteria::default();
+        assert_eq!(sliver_triangle.check_degeneracy(&criteria, 0.5), None);
+        let criteria = DegeneracyCriteria {
+            min_relative_area: Some(0.1),
+            ..Default::default()
+        };
+        assert_eq!(
+            sliver_triangle.check_degeneracy(&criteria, 0.5),
+            Some(Degeneracy::RelativeArea)
+        );
+        let criteria = DegeneracyCriteria {
+            min_angle: Some(10f32),
+            ..Default::default()
+        };
+        assert_eq!(right_triangle.check_degeneracy(&criteria, 0.5), None);
+        assert_eq!(
+            sliver_triangle.check_degeneracy(&criteria, 0.5),
+            Some(Degeneracy::Angle)
+        );
+        let criteria = DegeneracyCriteria {
+            max_aspect_ratio: Some(10f32),
+            ..Default::default()
+        };
+        assert_eq!(right_triangle.check_degeneracy(&criteria, 0.5), None);
+        assert_eq!(
+            sliver_triangle.check_degeneracy(&criteria, 0.5),
+            Some(Degeneracy::AspectRatio)
+        );
+
+        // Zero length edge has undefined angle, rejected even without minimum area
+        let point = utils::Vec3f {
+            x: 1f32,
+            y: 0f32,
+            z: 0f32,
+        };
+        let collapsed_triangle = get_triangle(point, point);
+        assert!(collapsed_triangle.min_angle().is_nan());
+        let criteria = DegeneracyCriteria {
+            min_area: 0f32,
+            min_angle: Some(10f32),
+            ..Default::default()
+        };
+        assert_eq!(
+            collapsed_triangle.check_degeneracy(&criteria, 0.5),
+            Some(Degeneracy::Angle)
+        );
+    }
+
     #[test]
     fn normalizes_stl_cube() {
         let str_filename = String::from("examples/stl/cube.stl");
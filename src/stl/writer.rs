@@ -0,0 +1,58 @@
+use crate::stl::triangle::TriangleSTL;
+use crate::utils::create_folder_for_filename;
+use std::error::Error;
+use std::{fs, path};
+
+const HEADER_BYTES_SIZE: usize = 80;
+
+fn triangle_to_bytes(triangle: &TriangleSTL) -> Vec<u8> {
+    let mut bytes: Vec<u8> = Vec::with_capacity(50);
+    for v in [
+        triangle.normal,
+        triangle.point0,
+        triangle.point1,
+        triangle.point2,
+    ] {
+        bytes.extend(v.to_le_bytes_as_f32());
+    }
+    // Attribute byte count
+    bytes.extend([0u8; 2]);
+    return bytes;
+}
+
+/// Write triangles as binary STL
+pub fn write_stl(
+    filename: &path::Path,
+    triangles: &Vec<TriangleSTL>,
+) -> Result<(), Box<dyn Error>> {
+    create_folder_for_filename(filename)?;
+    let mut content: Vec<u8> = vec![0u8; HEADER_BYTES_SIZE];
+    content.extend((triangles.len() as u32).to_le_bytes());
+    for t in triangles.iter() {
+        content.extend(triangle_to_bytes(t));
+    }
+    fs::write(filename, content)?;
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stl::reader::read_stl;
+
+    #[test]
+    fn can_write_stl_cube() {
+        let filename = path::Path::new("examples/stl/cube.stl").to_owned();
+        let triangles = read_stl(&filename);
+
+        let out_filename = path::Path::new("output/stl/cube_copy.stl").to_owned();
+        write_stl(&out_filename, &triangles).unwrap();
+        let read_triangles = read_stl(&out_filename);
+        assert_eq!(read_triangles.len(), triangles.len());
+        for (t0, t1) in triangles.iter().zip(read_triangles.iter()) {
+            assert_eq!(t0.point0, t1.point0);
+            assert_eq!(t0.point2, t1.point2);
+            assert_eq!(t0.normal, t1.normal);
+        }
+    }
+}
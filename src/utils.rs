@@ -13,8 +13,9 @@ use std::{cmp::Ordering, convert::TryInto, error::Error, fmt, fs, hash, ops, pat
 
 const PREC_DIGITS: i32 = 5i32;
 
-fn truncate_float_to_int(f: f32, n_digits: i32) -> i32 {
-    let y = (f * 10f32.powi(n_digits)).round() as i32;
+/// Scale float by 10^n_digits and round it. Uses i64, so large coordinates don't saturate
+fn truncate_float_to_int(f: f32, n_digits: i32) -> i64 {
+    let y = (f as f64 * 10f64.powi(n_digits)).round() as i64;
     return y;
 }
 
@@ -305,3 +306,29 @@ impl hash::Hash for Vec3f {
         zt.hash(state);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn check_large_coordinates_not_merged() {
+        // UTM like coordinates, which saturated i32 after scaling
+        let p0 = Vec3f {
+            x: 500000f32,
+            y: 7400000f32,
+            z: 10f32,
+        };
+        let mut p1 = p0;
+        p1.x += 0.0625;
+        assert!(p0 != p1);
+        let points: HashSet<Vec3f> = [p0, p1, p0].into_iter().collect();
+        assert_eq!(points.len(), 2);
+    }
+
+    #[test]
+    fn check_almost_equal_small_values() {
+        assert!(almost_equal(1e-6, 2e-6));
+    }
+}